@@ -1,3 +1,7 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use super::mathutil;
 use super::FilterParameters;
 
 #[derive(Clone, Copy, Debug)]
@@ -6,6 +10,9 @@ pub struct Parameters {
     elements: Option<f64>, // number of elements
     storage: Option<f64>,  // storage (bits)
     hashes: Option<f64>,   // fingerprint size
+
+    // hyper parameter
+    counter_bits: f64, // c: width of each cell; 1 for a plain bit-array
 }
 
 impl FilterParameters for Parameters {
@@ -33,12 +40,14 @@ impl Parameters {
         elements: Option<u64>,
         storage: Option<u64>,
         hashes: Option<u64>,
+        counter_bits: Option<u64>,
     ) -> Parameters {
         let param = Parameters {
             error,
             elements: elements.map(|v| v as f64),
             storage: storage.map(|v| v as f64),
             hashes: hashes.map(|v| v as f64),
+            counter_bits: counter_bits.map(|v| v as f64).unwrap_or(1.0),
         };
 
         param.infer()
@@ -48,29 +57,33 @@ impl Parameters {
         for _ in 0..4 {
             self.storage.map(|storage| {
                 self.error.map(|error| {
-                    let c = f64::ln(2.0) * f64::ln(2.0);
-                    self.elements = Some(f64::floor(-(storage * c / f64::ln(error))));
+                    let c = mathutil::ln(2.0) * mathutil::ln(2.0);
+                    let cells = storage / self.counter_bits;
+                    self.elements = Some(mathutil::floor(-(cells * c / mathutil::ln(error))));
                 })
             });
 
             self.elements.map(|elements| {
                 self.error.map(|error| {
                     if self.storage.is_none() {
-                        let c = f64::ln(2.0) * f64::ln(2.0);
-                        self.storage = Some(f64::ceil(-elements * f64::ln(error) / c));
+                        let c = mathutil::ln(2.0) * mathutil::ln(2.0);
+                        let cells = mathutil::ceil(-elements * mathutil::ln(error) / c);
+                        self.storage = Some(cells * self.counter_bits);
                     };
                 });
 
                 self.storage.map(|storage| {
+                    let cells = storage / self.counter_bits;
+
                     if self.hashes.is_none() {
                         self.error = None; // affects the error
-                        self.hashes = Some(f64::round(storage / elements * f64::ln(2.0)));
+                        self.hashes = Some(mathutil::round(cells / elements * mathutil::ln(2.0)));
                     };
 
                     if self.error.is_none() {
-                        let c = f64::ln(2.0) * f64::ln(2.0);
+                        let c = mathutil::ln(2.0) * mathutil::ln(2.0);
                         self.storage = None; // affects storage
-                        self.error = Some((-(storage * c) / elements).exp());
+                        self.error = Some(mathutil::exp(-(cells * c) / elements));
                     }
                 });
             });
@@ -82,4 +95,100 @@ impl Parameters {
     pub fn hashes(&self) -> Option<u64> {
         self.hashes.map(|v| v as u64)
     }
+
+    /// Width, in bits, of each cell: `1` for a plain bit-array, or wider
+    /// for a saturating-counter `CountingFilter`.
+    pub fn counter_bits(&self) -> u64 {
+        self.counter_bits as u64
+    }
+}
+
+/// A Bloom filter whose `m` cells are saturating counters rather than
+/// single bits, so `remove` can undo a prior `add` without disturbing
+/// other elements sharing a cell. A counter that reaches the maximum
+/// value representable in `counter_bits` is pinned: further `remove`
+/// calls touching it are no-ops, trading exact deletion for permanence
+/// near saturation.
+#[derive(Clone, Debug)]
+pub struct CountingFilter {
+    hashes: usize,
+    max_count: u32,
+    counters: Vec<u32>,
+}
+
+impl CountingFilter {
+    /// Allocates a filter with `storage / counter_bits` cells, sized
+    /// from a fully resolved `Parameters`. Returns `None` if `params` is
+    /// missing the inferred `storage`/`hashes` fields.
+    pub fn new(params: &Parameters) -> Option<CountingFilter> {
+        let storage = params.storage()?;
+        let hashes = params.hashes()?;
+        let cells = (storage / params.counter_bits()).max(1) as usize;
+
+        let counter_bits = params.counter_bits();
+        let max_count = if counter_bits >= 32 {
+            u32::max_value()
+        } else {
+            (1u32 << counter_bits) - 1
+        };
+
+        Some(CountingFilter {
+            hashes: hashes as usize,
+            max_count,
+            counters: vec![0; cells],
+        })
+    }
+
+    /// Derives `hashes` cell indices for `x` by combining two independent
+    /// hashes (Kirsch-Mitzenmacher), avoiding `hashes` separate hashers.
+    fn indices<T: Hash>(&self, x: &T) -> Vec<usize> {
+        let mut h1 = DefaultHasher::new();
+        x.hash(&mut h1);
+        let h1 = h1.finish();
+
+        let mut h2 = DefaultHasher::new();
+        (!h1).hash(&mut h2);
+        let h2 = h2.finish();
+
+        (0..self.hashes)
+            .map(|i| (h1.wrapping_add((i as u64).wrapping_mul(h2))) as usize % self.counters.len())
+            .collect()
+    }
+
+    /// Inserts `x`, incrementing each of its `hashes` counters (pinned at
+    /// the saturating maximum).
+    pub fn add<T: Hash>(&mut self, x: &T) {
+        for idx in self.indices(x) {
+            if self.counters[idx] < self.max_count {
+                self.counters[idx] += 1;
+            }
+        }
+    }
+
+    /// Removes one occurrence of `x`, decrementing each of its counters.
+    /// Counters pinned at the saturating maximum are left untouched.
+    pub fn remove<T: Hash>(&mut self, x: &T) {
+        for idx in self.indices(x) {
+            if self.counters[idx] > 0 && self.counters[idx] < self.max_count {
+                self.counters[idx] -= 1;
+            }
+        }
+    }
+
+    /// Estimates how many times `x` is still present: the minimum across
+    /// its counters, as in a standard counting Bloom filter (an upper
+    /// bound, subject to the same false-positive risk as a membership
+    /// query).
+    pub fn estimate_count<T: Hash>(&self, x: &T) -> u32 {
+        self.indices(x)
+            .into_iter()
+            .map(|idx| self.counters[idx])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Whether `x` appears to be present (`estimate_count(x) > 0`).
+    pub fn contains<T: Hash>(&self, x: &T) -> bool {
+        self.estimate_count(x) > 0
+    }
 }
@@ -0,0 +1,150 @@
+use super::mathutil;
+use super::FilterParameters;
+
+/// A counting Bloom filter replaces each of the `m` single bits with a
+/// `c`-bit saturating counter, so elements can be removed again. Storage
+/// becomes `m * c`, and with `k` hashes the per-counter load under `n`
+/// elements is Poisson-distributed with mean `k*n/m`, giving an overflow
+/// risk `P(X >= 2^c)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Parameters {
+    // user defined / infered
+    error: Option<f64>,    // false positive rate
+    elements: Option<f64>, // number of elements
+    storage: Option<f64>,  // storage (bits)
+    hashes: Option<f64>,   // number of hash functions
+
+    // hyper parameter
+    counter_bits: f64, // c: width of each saturating counter
+}
+
+impl FilterParameters for Parameters {
+    fn error(&self) -> Option<f64> {
+        self.error
+    }
+
+    fn elements(&self) -> Option<u64> {
+        self.elements.map(|v| v as u64)
+    }
+
+    fn storage(&self) -> Option<u64> {
+        self.storage.map(|v| v as u64)
+    }
+
+    fn bits_per_element(&self) -> Option<f64> {
+        self.storage
+            .and_then(|storage| self.elements.map(|elements| storage / elements))
+    }
+}
+
+impl Parameters {
+    pub fn new(
+        error: Option<f64>,
+        elements: Option<u64>,
+        storage: Option<u64>,
+        hashes: Option<u64>,
+        counter_bits: Option<u64>,
+    ) -> Parameters {
+        let param = Parameters {
+            error,
+            elements: elements.map(|v| v as f64),
+            storage: storage.map(|v| v as f64),
+            hashes: hashes.map(|v| v as f64),
+            counter_bits: counter_bits.map(|v| v as f64).unwrap_or(4.0),
+        };
+
+        param.infer()
+    }
+
+    pub fn hashes(&self) -> Option<u64> {
+        self.hashes.map(|v| v as u64)
+    }
+
+    pub fn counter_bits(&self) -> u64 {
+        self.counter_bits as u64
+    }
+
+    /// Number of `m` cells backing the filter (the bit-array size a plain
+    /// Bloom filter with the same error/elements would use).
+    fn cells(&self) -> Option<f64> {
+        self.storage.map(|storage| storage / self.counter_bits)
+    }
+
+    /// Ceiling on the `P(X >= 2^c)` summation below: `counter_bits` comes
+    /// straight from user input with no upper bound, and the loop below is
+    /// `O(2^counter_bits)`, so without a cap a large enough value hangs the
+    /// computation. Any cap this generous is already well past the point
+    /// where `cdf` has converged to 1.0 for any `mean` this filter could
+    /// plausibly have, so it doesn't affect the reported probability.
+    const MAX_OVERFLOW_ITERATIONS: u64 = 1 << 24;
+
+    /// `P(X >= 2^c)` for `X ~ Poisson(k*n/m)`, the chance a counter
+    /// saturates (and so stops tracking further inserts/deletes exactly).
+    pub fn overflow_probability(&self) -> Option<f64> {
+        let hashes = self.hashes?;
+        let elements = self.elements?;
+        let cells = self.cells()?;
+
+        let mean = hashes * elements / cells;
+        let cap =
+            (mathutil::powf(2.0, self.counter_bits) as u64).min(Self::MAX_OVERFLOW_ITERATIONS);
+
+        // P(X < cap) for X ~ Poisson(mean), summed pmf term by term:
+        // pmf_0 = e^-mean, pmf_i = pmf_{i-1} * mean / i
+        let mut pmf = mathutil::exp(-mean);
+        let mut cdf = pmf;
+        for i in 1..cap {
+            pmf *= mean / (i as f64);
+            cdf += pmf;
+        }
+
+        Some(1.0 - cdf)
+    }
+
+    /// Bits/item penalty versus a plain (single-bit) Bloom filter sized
+    /// for the same error rate and element count: since both use the
+    /// same number of cells `m`, the counting variant simply costs
+    /// `counter_bits` times as much per cell.
+    pub fn bits_per_element_penalty(&self) -> f64 {
+        self.counter_bits
+    }
+
+    fn infer(mut self) -> Parameters {
+        for _ in 0..4 {
+            self.storage.map(|storage| {
+                self.error.map(|error| {
+                    let c = mathutil::ln(2.0) * mathutil::ln(2.0);
+                    let cells = storage / self.counter_bits;
+                    self.elements = Some(mathutil::floor(-(cells * c / mathutil::ln(error))));
+                })
+            });
+
+            self.elements.map(|elements| {
+                self.error.map(|error| {
+                    if self.storage.is_none() {
+                        let c = mathutil::ln(2.0) * mathutil::ln(2.0);
+                        let cells = mathutil::ceil(-elements * mathutil::ln(error) / c);
+                        self.storage = Some(cells * self.counter_bits);
+                    };
+                });
+
+                self.storage.map(|storage| {
+                    let cells = storage / self.counter_bits;
+
+                    if self.hashes.is_none() {
+                        self.error = None; // affects the error
+                        self.hashes = Some(mathutil::round(cells / elements * mathutil::ln(2.0)));
+                    };
+
+                    if self.error.is_none() {
+                        let c = mathutil::ln(2.0) * mathutil::ln(2.0);
+                        self.storage = None; // affects storage
+                        self.error = Some(mathutil::exp(-(cells * c) / elements));
+                    }
+                });
+            });
+        }
+
+        self
+    }
+}
@@ -1,3 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::MmapMut;
+use rand::Rng;
+
+use super::mathutil;
+use super::semisort::SemiSortedBucket;
 use super::FilterParameters;
 
 #[derive(Clone, Copy, Debug)]
@@ -13,9 +26,10 @@ pub struct Parameters {
 
     // hyper parameters
     save: f64,
-    hashes: f64, // possible buckets for each element (normally 2)
-    slots: f64,  // slots per bucket (e.g. 4)
-    util: f64,   // utilization, util \in (0, 1]
+    hashes: f64,  // possible buckets for each element (normally 2)
+    slots: f64,   // slots per bucket (e.g. 4)
+    util: f64,    // utilization, util \in (0, 1]
+    sorted: bool, // whether the semi-sorted bucket encoding is in play
 }
 
 impl FilterParameters for Parameters {
@@ -37,6 +51,17 @@ impl FilterParameters for Parameters {
     }
 }
 
+/// Rounds `buckets` up to the nearest power of two (at least 1).
+///
+/// `alternate_bucket_of`'s `(bucket ^ h(fingerprint)) % buckets` pairing
+/// is only its own inverse when `buckets` is a power of two; any other
+/// modulus can map a fingerprint to an `i2` whose own `alternate_bucket`
+/// doesn't recover `i1`, silently breaking the no-false-negatives
+/// guarantee under real eviction chains.
+fn round_up_pow2(buckets: f64) -> f64 {
+    (buckets.max(1.0) as u64).next_power_of_two() as f64
+}
+
 impl Parameters {
     /// Infers new parameters from contraints on:
     ///
@@ -77,7 +102,7 @@ impl Parameters {
             // log2(slots!) / slots
             let mut comp = 0.0;
             for i in 1..(slots + 1) {
-                comp += f64::log2(i as f64);
+                comp += mathutil::log2(i as f64);
             }
             comp / (slots as f64)
         } else {
@@ -94,6 +119,7 @@ impl Parameters {
             slots: slots as f64,
             util,
             save,
+            sorted,
         };
 
         if contraints == 2 {
@@ -123,6 +149,10 @@ impl Parameters {
         self.util
     }
 
+    pub fn sorted(&self) -> bool {
+        self.sorted
+    }
+
     fn incomplete(&self) -> bool {
         self.buckets.is_none()
             || self.error.is_none()
@@ -146,7 +176,7 @@ impl Parameters {
             self.fingerprint = self.fingerprint.or_else(|| {
                 self.elements.and_then(|elements| {
                     self.storage
-                        .and_then(|storage| Some(f64::floor((storage * self.util) / elements)))
+                        .and_then(|storage| Some(mathutil::floor((storage * self.util) / elements)))
                 })
             });
 
@@ -158,7 +188,7 @@ impl Parameters {
             self.fingerprint = self.fingerprint.or_else(|| {
                 self.error.and_then(|error| {
                     self.error = None; // this affects the error (may decrease)
-                    Some(f64::ceil(f64::log2(
+                    Some(mathutil::ceil(mathutil::log2(
                         self.util * self.slots * self.hashes / error,
                     )))
                 })
@@ -170,8 +200,8 @@ impl Parameters {
                 //  - number of slots per bucket
                 //  - utilization
                 self.error = self.error.or_else(|| {
-                    let ok_one = 1. - f64::powf(2.0, -fingerprint);
-                    let ok_all = ok_one.powf(self.slots * self.hashes * self.util);
+                    let ok_one = 1. - mathutil::powf(2.0, -fingerprint);
+                    let ok_all = mathutil::powf(ok_one, self.slots * self.hashes * self.util);
                     Some(1. - ok_all)
                 });
 
@@ -181,8 +211,8 @@ impl Parameters {
                 //  - util
                 self.buckets = self.buckets.or_else(|| {
                     self.elements.and_then(|elements| {
-                        let cells = f64::ceil(elements / self.util);
-                        Some(f64::ceil(cells / self.slots))
+                        let cells = mathutil::ceil(elements / self.util);
+                        Some(round_up_pow2(mathutil::ceil(cells / self.slots)))
                     })
                 });
 
@@ -192,8 +222,8 @@ impl Parameters {
                 //  - slots per bucket
                 self.buckets = self.buckets.or_else(|| {
                     self.storage.and_then(|storage| {
-                        let cells = f64::floor(storage / fingerprint);
-                        Some(f64::floor(cells / self.slots))
+                        let cells = mathutil::floor(storage / fingerprint);
+                        Some(round_up_pow2(mathutil::floor(cells / self.slots)))
                     })
                 });
 
@@ -215,7 +245,7 @@ impl Parameters {
             self.elements = self.elements.or_else(|| {
                 self.buckets.and_then(|buckets| {
                     let cells = buckets * self.slots;
-                    Some(f64::floor(cells * self.util))
+                    Some(mathutil::floor(cells * self.util))
                 })
             })
         }
@@ -223,3 +253,855 @@ impl Parameters {
         self
     }
 }
+
+/// Maximum number of evictions ("kicks") `Filter::insert` will attempt
+/// before giving up and reporting the filter as full.
+pub const MAX_SEARCH: usize = 500;
+
+fn hash_value<T: Hash>(x: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    x.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Derives a non-zero fingerprint for `x` (zero is reserved to mean
+/// "empty slot"), masked to `fp_mask`'s width.
+fn fingerprint_of<T: Hash>(x: &T, fp_mask: u64) -> u64 {
+    let fp = hash_value(x) & fp_mask;
+    if fp == 0 {
+        1
+    } else {
+        fp
+    }
+}
+
+fn primary_bucket_of<T: Hash>(x: &T, buckets: usize) -> usize {
+    (hash_value(x) >> 32) as usize % buckets
+}
+
+/// `i2 = i1 XOR h(fingerprint)`, so the same computation run against
+/// either bucket recovers the other one.
+fn alternate_bucket_of(bucket: usize, fingerprint: u64, buckets: usize) -> usize {
+    (bucket ^ hash_value(&fingerprint) as usize) % buckets
+}
+
+/// The backing store for a `Filter`'s buckets: either one cell per slot,
+/// or (when `Parameters::sorted()`) a `SemiSortedBucket`-packed integer
+/// per bucket, whose value fits in `SemiSortedBucket::packed_bits` bits.
+/// Each packed bucket is still held in a full `u128`, so memory use is
+/// not reduced to that packed size.
+#[derive(Clone, Debug)]
+enum Storage {
+    Plain(Vec<Option<u64>>),
+    Compact {
+        codec: SemiSortedBucket,
+        buckets: Vec<u128>,
+    },
+}
+
+/// A partial-key cuckoo filter: each element is represented only by its
+/// `fingerprint`, stored in one of `slots` cells in one of two candidate
+/// buckets. Sized from an already-inferred `cuckoo::Parameters`.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    buckets: usize,
+    slots: usize,
+    fp_mask: u64,
+    fingerprint_bits: u32,
+    storage: Storage,
+
+    /// Per-slot reference counts for the `add`/`remove`/`estimate_count`
+    /// counting mode, letting a multiply-`add`ed element require the
+    /// same number of `remove` calls before it disappears. `None` for
+    /// semi-sorted (`Parameters::sorted()`) filters: the dense packing
+    /// used there has no spare bits to carry a count.
+    refcounts: Option<Vec<u8>>,
+}
+
+/// Returned by `Filter::insert` when the eviction chain exceeds
+/// `MAX_SEARCH` kicks; the caller should resize and retry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Full;
+
+impl Filter {
+    /// Allocates a filter with `buckets * slots` cells, sized from a
+    /// fully resolved `Parameters`. Returns `None` if `params` is missing
+    /// the inferred `buckets`/`fingerprint` fields.
+    pub fn new(params: &Parameters) -> Option<Filter> {
+        let buckets = params.buckets()? as usize;
+        let slots = params.slots() as usize;
+        let fingerprint = params.fingerprint()?;
+
+        let fp_mask = if fingerprint >= 64 {
+            u64::max_value()
+        } else {
+            (1u64 << fingerprint) - 1
+        };
+
+        let storage = if params.sorted() {
+            let codec = SemiSortedBucket::new(slots);
+            Storage::Compact {
+                buckets: vec![0u128; buckets],
+                codec,
+            }
+        } else {
+            Storage::Plain(vec![None; buckets * slots])
+        };
+
+        let refcounts = match &storage {
+            Storage::Plain(_) => Some(vec![0u8; buckets * slots]),
+            Storage::Compact { .. } => None,
+        };
+
+        Some(Filter {
+            buckets,
+            slots,
+            fp_mask,
+            fingerprint_bits: fingerprint as u32,
+            storage,
+            refcounts,
+        })
+    }
+
+    fn hash<T: Hash>(x: &T) -> u64 {
+        hash_value(x)
+    }
+
+    /// Derives a non-zero `fingerprint` for `x` (zero is reserved to mean
+    /// "empty slot").
+    fn fingerprint<T: Hash>(&self, x: &T) -> u64 {
+        fingerprint_of(x, self.fp_mask)
+    }
+
+    fn primary_bucket<T: Hash>(&self, x: &T) -> usize {
+        primary_bucket_of(x, self.buckets)
+    }
+
+    /// `i2 = i1 XOR h(fingerprint)`, so the same computation run against
+    /// either bucket recovers the other one.
+    fn alternate_bucket(&self, bucket: usize, fingerprint: u64) -> usize {
+        alternate_bucket_of(bucket, fingerprint, self.buckets)
+    }
+
+    fn slots_of(&self, bucket: usize) -> Range<usize> {
+        let base = bucket * self.slots;
+        base..base + self.slots
+    }
+
+    /// Index of the slot in `bucket` holding `fingerprint`, if any.
+    /// Always `None` for `Storage::Compact`, which has no stable slot
+    /// indices to report.
+    fn find_slot(&self, bucket: usize, fingerprint: u64) -> Option<usize> {
+        match &self.storage {
+            Storage::Plain(cells) => self.slots_of(bucket).find(|&idx| cells[idx] == Some(fingerprint)),
+            Storage::Compact { .. } => None,
+        }
+    }
+
+    fn try_place(&mut self, bucket: usize, fingerprint: u64) -> bool {
+        match &mut self.storage {
+            Storage::Plain(cells) => {
+                for idx in (bucket * self.slots)..(bucket * self.slots + self.slots) {
+                    if cells[idx].is_none() {
+                        cells[idx] = Some(fingerprint);
+                        return true;
+                    }
+                }
+                false
+            }
+            Storage::Compact { codec, buckets } => {
+                let fingerprint_bits = self.fingerprint_bits;
+                let mut fps = codec.unpack(buckets[bucket], fingerprint_bits);
+                if let Some(slot) = fps.iter().position(|&fp| fp == 0) {
+                    fps[slot] = fingerprint;
+                    buckets[bucket] = codec.pack(&fps, fingerprint_bits);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn bucket_contains(&self, bucket: usize, fingerprint: u64) -> bool {
+        match &self.storage {
+            Storage::Plain(cells) => self
+                .slots_of(bucket)
+                .any(|idx| cells[idx] == Some(fingerprint)),
+            Storage::Compact { codec, buckets } => {
+                codec.contains(buckets[bucket], self.fingerprint_bits, fingerprint)
+            }
+        }
+    }
+
+    fn remove_from_bucket(&mut self, bucket: usize, fingerprint: u64) -> bool {
+        match &mut self.storage {
+            Storage::Plain(cells) => {
+                for idx in (bucket * self.slots)..(bucket * self.slots + self.slots) {
+                    if cells[idx] == Some(fingerprint) {
+                        cells[idx] = None;
+                        return true;
+                    }
+                }
+                false
+            }
+            Storage::Compact { codec, buckets } => {
+                let fingerprint_bits = self.fingerprint_bits;
+                let mut fps = codec.unpack(buckets[bucket], fingerprint_bits);
+                if let Some(slot) = fps.iter().position(|&fp| fp == fingerprint) {
+                    fps[slot] = 0;
+                    buckets[bucket] = codec.pack(&fps, fingerprint_bits);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Evicts whatever fingerprint occupies a random slot of `bucket`,
+    /// replacing it with `fingerprint`, and returns the evicted value.
+    fn evict(&mut self, bucket: usize, fingerprint: u64, rng: &mut impl Rng) -> u64 {
+        match &mut self.storage {
+            Storage::Plain(cells) => {
+                let slot = bucket * self.slots + rng.gen_range(0, self.slots);
+                cells[slot].replace(fingerprint).unwrap()
+            }
+            Storage::Compact { codec, buckets } => {
+                let fingerprint_bits = self.fingerprint_bits;
+                let mut fps = codec.unpack(buckets[bucket], fingerprint_bits);
+                let slot = rng.gen_range(0, self.slots);
+                let evicted = fps[slot];
+                fps[slot] = fingerprint;
+                buckets[bucket] = codec.pack(&fps, fingerprint_bits);
+                evicted
+            }
+        }
+    }
+
+    /// Inserts `x`, placing its fingerprint in either candidate bucket if
+    /// a slot is free, otherwise repeatedly evicting a random fingerprint
+    /// and re-homing it (bounded by `MAX_SEARCH` kicks).
+    pub fn insert<T: Hash>(&mut self, x: &T) -> Result<(), Full> {
+        let fingerprint = self.fingerprint(x);
+        let i1 = self.primary_bucket(x);
+        let i2 = self.alternate_bucket(i1, fingerprint);
+
+        if self.try_place(i1, fingerprint) || self.try_place(i2, fingerprint) {
+            return Ok(());
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut bucket = if rng.gen() { i1 } else { i2 };
+        let mut fingerprint = fingerprint;
+
+        for _ in 0..MAX_SEARCH {
+            fingerprint = self.evict(bucket, fingerprint, &mut rng);
+            bucket = self.alternate_bucket(bucket, fingerprint);
+
+            if self.try_place(bucket, fingerprint) {
+                return Ok(());
+            }
+        }
+
+        Err(Full)
+    }
+
+    /// Reports whether `x` is (probably) a member of the filter.
+    pub fn contains<T: Hash>(&self, x: &T) -> bool {
+        let fingerprint = self.fingerprint(x);
+        let i1 = self.primary_bucket(x);
+        let i2 = self.alternate_bucket(i1, fingerprint);
+        self.bucket_contains(i1, fingerprint) || self.bucket_contains(i2, fingerprint)
+    }
+
+    /// Removes one matching fingerprint for `x` from either candidate
+    /// bucket. Returns `false` if none was found.
+    pub fn delete<T: Hash>(&mut self, x: &T) -> bool {
+        let fingerprint = self.fingerprint(x);
+        let i1 = self.primary_bucket(x);
+        let i2 = self.alternate_bucket(i1, fingerprint);
+        self.remove_from_bucket(i1, fingerprint) || self.remove_from_bucket(i2, fingerprint)
+    }
+
+    /// Like `evict`, but also swaps the per-slot refcount along with the
+    /// fingerprint, so a counted element keeps its count as it is kicked
+    /// from bucket to bucket.
+    fn evict_counting(
+        &mut self,
+        bucket: usize,
+        fingerprint: u64,
+        refcount: u8,
+        rng: &mut impl Rng,
+    ) -> (u64, u8) {
+        let cells = match &mut self.storage {
+            Storage::Plain(cells) => cells,
+            Storage::Compact { .. } => {
+                unreachable!("add/remove/estimate_count require a non-sorted Filter")
+            }
+        };
+        let slot = bucket * self.slots + rng.gen_range(0, self.slots);
+        let evicted_fingerprint = cells[slot].replace(fingerprint).unwrap();
+        let refcounts = self.refcounts.as_mut().unwrap();
+        let evicted_refcount = std::mem::replace(&mut refcounts[slot], refcount);
+        (evicted_fingerprint, evicted_refcount)
+    }
+
+    fn set_refcount(&mut self, bucket: usize, fingerprint: u64, value: u8) {
+        let idx = self
+            .find_slot(bucket, fingerprint)
+            .expect("fingerprint was just placed in this bucket");
+        self.refcounts.as_mut().unwrap()[idx] = value;
+    }
+
+    /// Inserts one occurrence of `x`. If `x`'s fingerprint already
+    /// occupies a slot in either candidate bucket, its refcount is
+    /// incremented (saturating, pinned at `u8::MAX`) instead of
+    /// consuming a fresh slot: `k` calls to `add` require `k` calls to
+    /// `remove` before `x` disappears. Only supported for plain (not
+    /// `Parameters::sorted()`) filters.
+    pub fn add<T: Hash>(&mut self, x: &T) -> Result<(), Full> {
+        assert!(
+            self.refcounts.is_some(),
+            "add/remove/estimate_count require a non-sorted Filter"
+        );
+
+        let fingerprint = self.fingerprint(x);
+        let i1 = self.primary_bucket(x);
+        let i2 = self.alternate_bucket(i1, fingerprint);
+
+        if let Some(idx) = self
+            .find_slot(i1, fingerprint)
+            .or_else(|| self.find_slot(i2, fingerprint))
+        {
+            let refcounts = self.refcounts.as_mut().unwrap();
+            if refcounts[idx] < u8::max_value() {
+                refcounts[idx] += 1;
+            }
+            return Ok(());
+        }
+
+        if self.try_place(i1, fingerprint) {
+            self.set_refcount(i1, fingerprint, 1);
+            return Ok(());
+        }
+        if self.try_place(i2, fingerprint) {
+            self.set_refcount(i2, fingerprint, 1);
+            return Ok(());
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut bucket = if rng.gen() { i1 } else { i2 };
+        let mut fingerprint = fingerprint;
+        let mut refcount = 1u8;
+
+        for _ in 0..MAX_SEARCH {
+            let (evicted_fingerprint, evicted_refcount) =
+                self.evict_counting(bucket, fingerprint, refcount, &mut rng);
+            fingerprint = evicted_fingerprint;
+            refcount = evicted_refcount;
+            bucket = self.alternate_bucket(bucket, fingerprint);
+
+            if self.try_place(bucket, fingerprint) {
+                self.set_refcount(bucket, fingerprint, refcount);
+                return Ok(());
+            }
+        }
+
+        Err(Full)
+    }
+
+    /// Removes one occurrence of `x`, decrementing its refcount; the slot
+    /// is only actually freed once the refcount reaches zero. A refcount
+    /// pinned at `u8::MAX` (saturated) can no longer be decremented and
+    /// the occurrence becomes permanent. Returns `false` if `x` has no
+    /// remaining occurrences or its refcount is pinned.
+    pub fn remove<T: Hash>(&mut self, x: &T) -> bool {
+        let fingerprint = self.fingerprint(x);
+        let i1 = self.primary_bucket(x);
+        let i2 = self.alternate_bucket(i1, fingerprint);
+
+        let idx = match self
+            .find_slot(i1, fingerprint)
+            .or_else(|| self.find_slot(i2, fingerprint))
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        let refcounts = self
+            .refcounts
+            .as_mut()
+            .expect("add/remove/estimate_count require a non-sorted Filter");
+        if refcounts[idx] == u8::max_value() {
+            return false;
+        }
+
+        refcounts[idx] -= 1;
+        if refcounts[idx] == 0 {
+            if let Storage::Plain(cells) = &mut self.storage {
+                cells[idx] = None;
+            }
+        }
+        true
+    }
+
+    /// Estimates how many outstanding `add`s `x` has (`0` if absent), via
+    /// its slot's refcount. A refcount pinned at `u8::MAX` may under-report
+    /// the true count.
+    pub fn estimate_count<T: Hash>(&self, x: &T) -> u8 {
+        let fingerprint = self.fingerprint(x);
+        let i1 = self.primary_bucket(x);
+        let i2 = self.alternate_bucket(i1, fingerprint);
+
+        self.find_slot(i1, fingerprint)
+            .or_else(|| self.find_slot(i2, fingerprint))
+            .map(|idx| self.refcounts.as_ref().unwrap()[idx])
+            .unwrap_or(0)
+    }
+
+    /// Number of occupied cells.
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Plain(cells) => cells.iter().filter(|c| c.is_some()).count(),
+            Storage::Compact { codec, buckets } => {
+                let fingerprint_bits = self.fingerprint_bits;
+                buckets
+                    .iter()
+                    .map(|&packed| {
+                        codec
+                            .unpack(packed, fingerprint_bits)
+                            .iter()
+                            .filter(|&&fp| fp != 0)
+                            .count()
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Default initial capacity for a `MappedFilter`, as a power of two
+/// number of buckets (`2^10 = 1024`): small enough that an empty filter
+/// doesn't reserve pages it will never touch, large enough that most
+/// callers never see a `grow`.
+pub const DEFAULT_CAPACITY_POW2: u32 = 10;
+
+const MAGIC: [u8; 8] = *b"CUCKOOv1";
+const HEADER_LEN: usize = 36;
+
+/// A cuckoo filter backed by a memory-mapped file instead of an
+/// in-process `Vec`, so it survives the process and grows instead of
+/// reporting `Full`. The file holds a small header (the resolved
+/// `fingerprint`/`slots`/`util` needed to reopen it, see `open`)
+/// followed by one little-endian `u64` per cell, `0` meaning empty —
+/// the same sentinel `Filter` uses.
+pub struct MappedFilter {
+    // Never read again, but must outlive `mmap` for the mapping to stay valid.
+    #[allow(dead_code)]
+    file: File,
+    mmap: MmapMut,
+    fingerprint_bits: u32,
+    fp_mask: u64,
+    slots: usize,
+    buckets: usize,
+    util: f64,
+}
+
+impl MappedFilter {
+    fn write_header(mmap: &mut MmapMut, fingerprint_bits: u32, slots: usize, buckets_pow2: u32, util: f64) {
+        mmap[0..8].copy_from_slice(&MAGIC);
+        mmap[8..16].copy_from_slice(&(fingerprint_bits as u64).to_le_bytes());
+        mmap[16..24].copy_from_slice(&(slots as u64).to_le_bytes());
+        mmap[24..28].copy_from_slice(&buckets_pow2.to_le_bytes());
+        mmap[28..36].copy_from_slice(&util.to_bits().to_le_bytes());
+    }
+
+    fn map_file(file: &File) -> io::Result<MmapMut> {
+        // Safety: the file is exclusively owned by this `MappedFilter`
+        // (or the temporary one built by `grow`), so no other process or
+        // mapping can race the writes made through `mmap`.
+        unsafe { MmapMut::map_mut(file) }
+    }
+
+    fn allocate(path: &Path, fingerprint_bits: u32, slots: usize, buckets_pow2: u32, util: f64) -> io::Result<(File, MmapMut)> {
+        let buckets = 1usize << buckets_pow2;
+        let len = HEADER_LEN + buckets * slots * 8;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(len as u64)?;
+
+        let mut mmap = Self::map_file(&file)?;
+        Self::write_header(&mut mmap, fingerprint_bits, slots, buckets_pow2, util);
+
+        Ok((file, mmap))
+    }
+
+    /// Creates a new mmap-backed filter at `path`, sized from a fully
+    /// resolved `Parameters`, starting at `DEFAULT_CAPACITY_POW2`
+    /// buckets.
+    pub fn create(path: &Path, params: &Parameters) -> io::Result<MappedFilter> {
+        let fingerprint_bits = params
+            .fingerprint()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Parameters missing fingerprint"))?
+            as u32;
+        let slots = params.slots() as usize;
+        let util = params.util();
+
+        let (file, mmap) = Self::allocate(path, fingerprint_bits, slots, DEFAULT_CAPACITY_POW2, util)?;
+        let fp_mask = fp_mask_of(fingerprint_bits);
+
+        Ok(MappedFilter {
+            file,
+            mmap,
+            fingerprint_bits,
+            fp_mask,
+            slots,
+            buckets: 1usize << DEFAULT_CAPACITY_POW2,
+            util,
+        })
+    }
+
+    /// Reopens a filter previously written by `create`, reading its
+    /// parameters back out of the header.
+    pub fn open(path: &Path) -> io::Result<MappedFilter> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mmap = Self::map_file(&file)?;
+
+        if mmap.len() < HEADER_LEN || mmap[0..8] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a cuckoo filter file"));
+        }
+
+        let fingerprint_bits = u64::from_le_bytes(mmap[8..16].try_into().unwrap()) as u32;
+        let slots = u64::from_le_bytes(mmap[16..24].try_into().unwrap()) as usize;
+        let buckets_pow2 = u32::from_le_bytes(mmap[24..28].try_into().unwrap());
+        let util = f64::from_bits(u64::from_le_bytes(mmap[28..36].try_into().unwrap()));
+
+        Ok(MappedFilter {
+            file,
+            mmap,
+            fingerprint_bits,
+            fp_mask: fp_mask_of(fingerprint_bits),
+            slots,
+            buckets: 1usize << buckets_pow2,
+            util,
+        })
+    }
+
+    /// Flushes pending writes to disk.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.mmap.flush()
+    }
+
+    fn cell(&self, idx: usize) -> u64 {
+        let off = HEADER_LEN + idx * 8;
+        u64::from_le_bytes(self.mmap[off..off + 8].try_into().unwrap())
+    }
+
+    fn set_cell(&mut self, idx: usize, value: u64) {
+        let off = HEADER_LEN + idx * 8;
+        self.mmap[off..off + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn try_place(&mut self, bucket: usize, fingerprint: u64) -> bool {
+        for idx in (bucket * self.slots)..(bucket * self.slots + self.slots) {
+            if self.cell(idx) == 0 {
+                self.set_cell(idx, fingerprint);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn bucket_contains(&self, bucket: usize, fingerprint: u64) -> bool {
+        (bucket * self.slots..bucket * self.slots + self.slots).any(|idx| self.cell(idx) == fingerprint)
+    }
+
+    fn remove_from_bucket(&mut self, bucket: usize, fingerprint: u64) -> bool {
+        for idx in (bucket * self.slots)..(bucket * self.slots + self.slots) {
+            if self.cell(idx) == fingerprint {
+                self.set_cell(idx, 0);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Places `fingerprint` starting from candidate bucket `i1`,
+    /// evicting and re-homing (bounded by `MAX_SEARCH` kicks) if both
+    /// `i1` and its alternate are full. On failure, returns the
+    /// fingerprint left without a slot when the kicks ran out.
+    fn try_insert_fingerprint(&mut self, i1: usize, fingerprint: u64) -> Result<(), u64> {
+        let i2 = alternate_bucket_of(i1, fingerprint, self.buckets);
+
+        if self.try_place(i1, fingerprint) || self.try_place(i2, fingerprint) {
+            return Ok(());
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut bucket = if rng.gen() { i1 } else { i2 };
+        let mut fingerprint = fingerprint;
+
+        for _ in 0..MAX_SEARCH {
+            let slot = bucket * self.slots + rng.gen_range(0, self.slots);
+            let evicted = self.cell(slot);
+            self.set_cell(slot, fingerprint);
+            fingerprint = evicted;
+            bucket = alternate_bucket_of(bucket, fingerprint, self.buckets);
+
+            if self.try_place(bucket, fingerprint) {
+                return Ok(());
+            }
+        }
+
+        Err(fingerprint)
+    }
+
+    /// Inserts `x`, growing to the next power-of-two bucket count (and
+    /// rehashing every live fingerprint) if the eviction budget is
+    /// exhausted — unlike `Filter::insert`, this never reports `Full`.
+    ///
+    /// Unlike `Filter`, the primary bucket is derived from `fingerprint`
+    /// rather than from `x` directly (see the note on `grow`): `x` isn't
+    /// available to rehash by once only the fingerprint is persisted, so
+    /// every candidate-bucket computation here has to be a pure function
+    /// of the fingerprint to stay reproducible after a resize.
+    pub fn insert<T: Hash>(&mut self, path: &Path, x: &T) -> io::Result<()> {
+        let fingerprint = fingerprint_of(x, self.fp_mask);
+        let i1 = primary_bucket_of(&fingerprint, self.buckets);
+
+        match self.try_insert_fingerprint(i1, fingerprint) {
+            Ok(()) => Ok(()),
+            Err(homeless) => {
+                self.grow(path)?;
+                let i1 = primary_bucket_of(&homeless, self.buckets);
+                self.try_insert_fingerprint(i1, homeless)
+                    .map_err(|_| io::Error::new(io::ErrorKind::Other, "insert failed even after growing"))
+            }
+        }
+    }
+
+    /// Reports whether `x` is (probably) a member of the filter.
+    pub fn contains<T: Hash>(&self, x: &T) -> bool {
+        let fingerprint = fingerprint_of(x, self.fp_mask);
+        let i1 = primary_bucket_of(&fingerprint, self.buckets);
+        let i2 = alternate_bucket_of(i1, fingerprint, self.buckets);
+        self.bucket_contains(i1, fingerprint) || self.bucket_contains(i2, fingerprint)
+    }
+
+    /// Removes one matching fingerprint for `x`. Returns `false` if none
+    /// was found.
+    pub fn delete<T: Hash>(&mut self, x: &T) -> bool {
+        let fingerprint = fingerprint_of(x, self.fp_mask);
+        let i1 = primary_bucket_of(&fingerprint, self.buckets);
+        let i2 = alternate_bucket_of(i1, fingerprint, self.buckets);
+        self.remove_from_bucket(i1, fingerprint) || self.remove_from_bucket(i2, fingerprint)
+    }
+
+    /// Number of occupied cells.
+    pub fn len(&self) -> usize {
+        (0..self.buckets * self.slots).filter(|&idx| self.cell(idx) != 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Doubles the bucket count, rehashing every live fingerprint into a
+    /// fresh file next to `path`, then atomically swaps it in.
+    ///
+    /// Since only fingerprints — not the original keys — are stored, a
+    /// live fingerprint can only be rehomed from something derived from
+    /// the fingerprint itself, not from the key that produced it. That's
+    /// exactly what `insert`/`contains`/`delete` already assume: `i1` is
+    /// `primary_bucket_of(&fingerprint, self.buckets)`, so recomputing it
+    /// against `new_buckets` here and reinserting via
+    /// `try_insert_fingerprint` (same as a fresh `insert` would) lands
+    /// the fingerprint at a candidate pair that `contains`/`delete` will
+    /// independently rederive afterwards.
+    fn grow(&mut self, path: &Path) -> io::Result<()> {
+        let old_buckets = self.buckets;
+        let new_buckets_pow2 = (old_buckets.trailing_zeros()) + 1;
+        let new_buckets = 1usize << new_buckets_pow2;
+
+        let tmp_path = path.with_extension("grow");
+        let (file, mmap) = Self::allocate(&tmp_path, self.fingerprint_bits, self.slots, new_buckets_pow2, self.util)?;
+
+        let mut grown = MappedFilter {
+            file,
+            mmap,
+            fingerprint_bits: self.fingerprint_bits,
+            fp_mask: self.fp_mask,
+            slots: self.slots,
+            buckets: new_buckets,
+            util: self.util,
+        };
+
+        for idx in 0..old_buckets * self.slots {
+            let fingerprint = self.cell(idx);
+            if fingerprint == 0 {
+                continue;
+            }
+
+            let candidate = primary_bucket_of(&fingerprint, new_buckets);
+            if grown.try_insert_fingerprint(candidate, fingerprint).is_err() {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "rehash did not fit after doubling capacity",
+                ));
+            }
+        }
+
+        grown.flush()?;
+        std::fs::rename(&tmp_path, path)?;
+
+        *self = grown;
+        Ok(())
+    }
+}
+
+fn fp_mask_of(fingerprint_bits: u32) -> u64 {
+    if fingerprint_bits >= 64 {
+        u64::max_value()
+    } else {
+        (1u64 << fingerprint_bits) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(sorted: bool) -> Parameters {
+        Parameters::new(Some(0.01), Some(2000), None, 2, 4, 0.95, sorted)
+    }
+
+    #[test]
+    fn insert_contains_delete_plain() {
+        let mut filter = Filter::new(&params(false)).unwrap();
+        let items: Vec<u64> = (0..1000).collect();
+
+        for x in &items {
+            filter.insert(x).unwrap();
+        }
+        for x in &items {
+            assert!(filter.contains(x), "missing {} right after insert", x);
+        }
+        assert_eq!(filter.len(), items.len());
+
+        for x in &items {
+            assert!(filter.delete(x), "delete {} should find a match", x);
+        }
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn insert_contains_delete_sorted() {
+        // Same as `insert_contains_delete_plain`, but exercises the
+        // semi-sorted `Storage::Compact` path (regression coverage for
+        // the pack/low-nibble-sort bug this mode used to panic on).
+        let mut filter = Filter::new(&params(true)).unwrap();
+        let items: Vec<u64> = (0..1000).collect();
+
+        for x in &items {
+            filter.insert(x).unwrap();
+        }
+        for x in &items {
+            assert!(filter.contains(x), "missing {} right after insert", x);
+        }
+        assert_eq!(filter.len(), items.len());
+
+        for x in &items {
+            assert!(filter.delete(x), "delete {} should find a match", x);
+        }
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn add_remove_tracks_refcount() {
+        let mut filter = Filter::new(&params(false)).unwrap();
+        let x = "counted";
+
+        for _ in 0..5 {
+            filter.add(&x).unwrap();
+        }
+        assert_eq!(filter.estimate_count(&x), 5);
+        assert!(filter.contains(&x));
+
+        for n in (0..5).rev() {
+            assert!(filter.remove(&x));
+            assert_eq!(filter.estimate_count(&x), n);
+        }
+        assert!(!filter.contains(&x));
+        assert!(!filter.remove(&x));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-sorted Filter")]
+    fn add_on_sorted_filter_panics() {
+        let mut filter = Filter::new(&params(true)).unwrap();
+        let _ = filter.add(&"x");
+    }
+
+    fn mapped_test_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("probset_cuckoo_test_{}_{}.bin", name, std::process::id()))
+    }
+
+    #[test]
+    fn mapped_filter_create_insert_contains_delete() {
+        let path = mapped_test_path("basic");
+        let _ = std::fs::remove_file(&path);
+
+        let mut filter = MappedFilter::create(&path, &params(false)).unwrap();
+        let items: Vec<u64> = (0..500).collect();
+
+        for x in &items {
+            filter.insert(&path, x).unwrap();
+        }
+        for x in &items {
+            assert!(filter.contains(x), "missing {} right after insert", x);
+        }
+        assert_eq!(filter.len(), items.len());
+
+        for x in &items {
+            assert!(filter.delete(x));
+        }
+        assert!(filter.is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn mapped_filter_survives_reopen_and_grow() {
+        let path = mapped_test_path("grow");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("grow"));
+
+        {
+            let mut filter = MappedFilter::create(&path, &params(false)).unwrap();
+            // Default capacity is 2^10 buckets * 4 slots; inserting well
+            // past that forces at least one `grow` mid-loop.
+            for x in 0u64..5000 {
+                filter.insert(&path, &x).unwrap();
+            }
+            filter.flush().unwrap();
+        }
+
+        let filter = MappedFilter::open(&path).unwrap();
+        for x in 0u64..5000 {
+            assert!(filter.contains(&x), "missing {} after reopen", x);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,34 @@
+//! Float primitives routed through `libm` instead of `std::f64` methods.
+//!
+//! This only covers the `Parameters::infer` math; the filter types
+//! themselves (`cuckoo::Filter`, `cuckoo::MappedFilter`, `semisort`, ...)
+//! still depend on `std` (`HashMap`, `fs`, `thread_rng`, ...), so this
+//! change alone does not make the crate `no_std`-compatible.
+
+pub(crate) fn ln(x: f64) -> f64 {
+    libm::log(x)
+}
+
+pub(crate) fn log2(x: f64) -> f64 {
+    libm::log2(x)
+}
+
+pub(crate) fn exp(x: f64) -> f64 {
+    libm::exp(x)
+}
+
+pub(crate) fn powf(base: f64, exponent: f64) -> f64 {
+    libm::pow(base, exponent)
+}
+
+pub(crate) fn floor(x: f64) -> f64 {
+    libm::floor(x)
+}
+
+pub(crate) fn ceil(x: f64) -> f64 {
+    libm::ceil(x)
+}
+
+pub(crate) fn round(x: f64) -> f64 {
+    libm::round(x)
+}
@@ -1,6 +1,11 @@
 pub mod bloom;
+pub mod counting_bloom;
 pub mod cuckoo;
+pub(crate) mod mathutil;
+pub mod morton;
+pub mod semisort;
 pub mod theory;
+pub mod xor;
 
 pub trait FilterParameters {
     fn error(&self) -> Option<f64>;
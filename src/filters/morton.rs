@@ -0,0 +1,244 @@
+use super::mathutil;
+use super::FilterParameters;
+
+/// A Morton filter packs fingerprints into compressed blocks: each
+/// physical block serves several logical buckets but only materializes
+/// the occupied slots, plus a small Fullness Counter Array (FCA) and
+/// Overflow Tracking Array (OTA) recording per-bucket occupancy. This
+/// decoupling lets it run at much higher load factors than a plain
+/// cuckoo filter.
+#[derive(Clone, Copy, Debug)]
+pub struct Parameters {
+    // user defined / infered
+    error: Option<f64>,    // false positive rate
+    elements: Option<f64>, // number of elements
+    storage: Option<f64>,  // storage (bits)
+
+    // infered optimal values
+    fingerprint: Option<f64>, // fingerprint size
+    blocks: Option<f64>,      // number of blocks
+
+    // hyper parameters
+    logical_slots: f64,     // b: logical slots per logical bucket
+    physical_slots: f64,    // physical slots materialized per block
+    buckets_per_block: f64, // logical buckets packed into a block
+    fca_bits: f64,          // Fullness Counter Array bits per logical bucket
+    ota_bits: f64,          // Overflow Tracking Array bits per logical bucket
+    load_factor: f64,       // achievable load factor (e.g. 0.95+)
+}
+
+impl FilterParameters for Parameters {
+    fn error(&self) -> Option<f64> {
+        self.error
+    }
+
+    fn elements(&self) -> Option<u64> {
+        self.elements.map(|v| v as u64)
+    }
+
+    fn storage(&self) -> Option<u64> {
+        self.storage.map(|v| v as u64)
+    }
+
+    fn bits_per_element(&self) -> Option<f64> {
+        self.storage
+            .and_then(|storage| self.elements.map(|elements| storage / elements))
+    }
+}
+
+impl Parameters {
+    /// Infers new parameters from contraints on:
+    ///
+    /// - error: the false positive rate.
+    /// - elements: the number of elements to store.
+    /// - storage: the number of bits for the filter.
+    ///
+    /// At most 2 of which may be supplied,
+    /// otherwise the system is over constrained.
+    ///
+    /// Additionally the following hyper parameters must be supplied:
+    ///
+    /// - logical_slots: logical slots per logical bucket (b)
+    /// - physical_slots: physical slots materialized per block
+    /// - buckets_per_block: logical buckets packed into one block
+    /// - fca_bits / ota_bits: per-bucket overhead of the occupancy arrays
+    /// - load_factor: achievable load factor (usually >=0.95)
+    ///
+    /// # Returns
+    ///
+    /// A full resolved set of optimal parameters.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        error: Option<f64>,
+        elements: Option<u64>,
+        storage: Option<u64>,
+        logical_slots: u64,
+        physical_slots: u64,
+        buckets_per_block: u64,
+        fca_bits: u64,
+        ota_bits: u64,
+        load_factor: f64,
+    ) -> Parameters {
+        let mut contraints = 0;
+
+        contraints += error.is_some() as u32;
+        contraints += elements.is_some() as u32;
+        contraints += storage.is_some() as u32;
+
+        let params = Parameters {
+            error,
+            elements: elements.map(|v| v as f64),
+            storage: storage.map(|v| v as f64),
+            fingerprint: None,
+            blocks: None,
+            logical_slots: logical_slots as f64,
+            physical_slots: physical_slots as f64,
+            buckets_per_block: buckets_per_block as f64,
+            fca_bits: fca_bits as f64,
+            ota_bits: ota_bits as f64,
+            load_factor,
+        };
+
+        if contraints == 2 {
+            params.infer()
+        } else {
+            params
+        }
+    }
+
+    pub fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint.map(|v| v as u64)
+    }
+
+    pub fn blocks(&self) -> Option<u64> {
+        self.blocks.map(|v| v as u64)
+    }
+
+    pub fn logical_slots(&self) -> u64 {
+        self.logical_slots as u64
+    }
+
+    pub fn physical_slots(&self) -> u64 {
+        self.physical_slots as u64
+    }
+
+    pub fn buckets_per_block(&self) -> u64 {
+        self.buckets_per_block as u64
+    }
+
+    pub fn fca_bits(&self) -> u64 {
+        self.fca_bits as u64
+    }
+
+    pub fn ota_bits(&self) -> u64 {
+        self.ota_bits as u64
+    }
+
+    pub fn load_factor(&self) -> f64 {
+        self.load_factor
+    }
+
+    fn logical_capacity_per_block(&self) -> f64 {
+        self.buckets_per_block * self.logical_slots
+    }
+
+    fn incomplete(&self) -> bool {
+        self.blocks.is_none()
+            || self.error.is_none()
+            || self.fingerprint.is_none()
+            || self.storage.is_none()
+            || self.elements.is_none()
+    }
+
+    fn infer(mut self) -> Parameters {
+        for _ in 0..8 {
+            if !self.incomplete() {
+                break;
+            }
+
+            // Infer fingerprint size, from:
+            //  - error
+            //  - logical slots per bucket
+            //
+            // f ~= ceil(log2(2*b/err))
+            self.fingerprint = self.fingerprint.or_else(|| {
+                self.error.and_then(|error| {
+                    self.error = None; // this affects the error (may decrease)
+                    Some(mathutil::ceil(mathutil::log2(2.0 * self.logical_slots / error)))
+                })
+            });
+
+            // Infer fingerprint size, from:
+            //  - storage
+            //  - elements
+            //  - load factor
+            self.fingerprint = self.fingerprint.or_else(|| {
+                self.elements.and_then(|elements| {
+                    self.storage.map(|storage| {
+                        let blocks = mathutil::ceil(
+                            elements / (self.load_factor * self.logical_capacity_per_block()),
+                        );
+                        let overhead = (self.fca_bits + self.ota_bits) * self.buckets_per_block;
+                        mathutil::floor((storage / blocks - overhead) / self.physical_slots)
+                    })
+                })
+            });
+
+            self.fingerprint.map(|fingerprint| {
+                // Infer false positive rate, from:
+                //  - fingerprint size
+                //  - logical slots per bucket
+                //
+                // err ~= 2*b / 2^f
+                self.error = self
+                    .error
+                    .or_else(|| Some(2.0 * self.logical_slots / mathutil::powf(2.0, fingerprint)));
+
+                // bits materialized per block: fingerprint bits for every
+                // physical slot, plus the FCA/OTA overhead for every
+                // logical bucket packed into the block.
+                let bits_per_block = fingerprint * self.physical_slots
+                    + (self.fca_bits + self.ota_bits) * self.buckets_per_block;
+
+                // Infer blocks, from:
+                //  - elements to store
+                //  - load factor
+                self.blocks = self.blocks.or_else(|| {
+                    self.elements.and_then(|elements| {
+                        Some(mathutil::ceil(
+                            elements / (self.load_factor * self.logical_capacity_per_block()),
+                        ))
+                    })
+                });
+
+                // Infer blocks, from:
+                //  - total storage
+                //  - bits materialized per block
+                self.blocks = self.blocks.or_else(|| {
+                    self.storage
+                        .and_then(|storage| Some(mathutil::floor(storage / bits_per_block)))
+                });
+
+                // Infer storage, from:
+                //  - number of blocks
+                //  - bits materialized per block
+                self.storage = self
+                    .storage
+                    .or_else(|| self.blocks.and_then(|blocks| Some(blocks * bits_per_block)));
+            });
+
+            // Infer number of stored elements, from:
+            //  - number of blocks
+            //  - load factor
+            self.elements = self.elements.or_else(|| {
+                self.blocks.and_then(|blocks| {
+                    Some(mathutil::floor(
+                        blocks * self.logical_capacity_per_block() * self.load_factor,
+                    ))
+                })
+            })
+        }
+
+        self
+    }
+}
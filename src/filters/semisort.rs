@@ -0,0 +1,188 @@
+//! Semi-sorted bucket encoding for cuckoo-style filters.
+//!
+//! Within a bucket, slot order carries no meaning — only the *multiset*
+//! of fingerprints matters for membership testing. `SemiSortedBucket`
+//! exploits this: the low-order nibble of each of the `slots`
+//! fingerprints is replaced by a single dense index into the (much
+//! smaller) set of non-decreasing nibble tuples, recovering the
+//! `log2(slots!)` bits of "which slot had which value" that a naive
+//! fixed-layout encoding wastes. High bits are stored verbatim, in the
+//! same sorted order used to pick the dense index.
+
+use std::collections::HashMap;
+
+/// Width, in bits, of the low part of each fingerprint that participates
+/// in the dense combination code.
+const LOW_BITS: u32 = 4;
+
+/// A precomputed encode/decode table mapping every non-decreasing tuple
+/// of `slots` low-order nibbles to a dense index, and back.
+#[derive(Clone, Debug)]
+pub struct SemiSortedBucket {
+    slots: usize,
+    code_bits: u32,
+    tuple_to_code: HashMap<Vec<u8>, u32>,
+    code_to_tuple: Vec<Vec<u8>>,
+}
+
+fn enumerate_tuples(slots: usize, alphabet: u8, start: u8, current: &mut Vec<u8>, out: &mut Vec<Vec<u8>>) {
+    if current.len() == slots {
+        out.push(current.clone());
+        return;
+    }
+    for v in start..alphabet {
+        current.push(v);
+        enumerate_tuples(slots, alphabet, v, current, out);
+        current.pop();
+    }
+}
+
+fn bits_for(combinations: u32) -> u32 {
+    let mut bits = 0;
+    while (1u32 << bits) < combinations {
+        bits += 1;
+    }
+    bits
+}
+
+/// Width, in bits, of the verbatim "high" part of a fingerprint once the
+/// low nibble is carved out for the dense code. Saturates at 0 for
+/// fingerprints no wider than `LOW_BITS`, which disables the low-bits
+/// split (every fingerprint shares the same — empty — high part) rather
+/// than underflowing.
+fn high_bits_of(fingerprint_bits: u32) -> u32 {
+    fingerprint_bits.saturating_sub(LOW_BITS)
+}
+
+impl SemiSortedBucket {
+    /// Builds the interner for buckets holding `slots` fingerprints.
+    pub fn new(slots: usize) -> SemiSortedBucket {
+        let alphabet = 1u8 << LOW_BITS;
+
+        let mut code_to_tuple = Vec::new();
+        enumerate_tuples(slots, alphabet, 0, &mut Vec::new(), &mut code_to_tuple);
+
+        let mut tuple_to_code = HashMap::with_capacity(code_to_tuple.len());
+        for (code, tuple) in code_to_tuple.iter().enumerate() {
+            tuple_to_code.insert(tuple.clone(), code as u32);
+        }
+
+        let code_bits = bits_for(code_to_tuple.len() as u32);
+
+        SemiSortedBucket {
+            slots,
+            code_bits,
+            tuple_to_code,
+            code_to_tuple,
+        }
+    }
+
+    /// Total bits a packed bucket occupies for fingerprints of the given
+    /// width: verbatim high bits for every slot, plus one dense code.
+    pub fn packed_bits(&self, fingerprint_bits: u32) -> u32 {
+        high_bits_of(fingerprint_bits) * self.slots as u32 + self.code_bits
+    }
+
+    /// Packs `fingerprints` (length must equal `slots`; an unused slot is
+    /// represented by `0`, which `cuckoo::Filter` already reserves to
+    /// mean "empty") into a single dense integer.
+    pub fn pack(&self, fingerprints: &[u64], fingerprint_bits: u32) -> u128 {
+        assert_eq!(fingerprints.len(), self.slots);
+
+        // `tuple_to_code` only contains tuples that are non-decreasing in
+        // the *low* nibble (that's what `enumerate_tuples` builds), so the
+        // sort key here must be the low nibble alone: sorting by the full
+        // fingerprint value can leave the low nibbles out of order (e.g.
+        // 0x01 before 0x10 sorts the lows as [1, 0]), which would look up a
+        // tuple that was never interned. A stable sort keeps `highs` lined
+        // up with `lows` for fingerprints that share a low nibble.
+        let low_mask = (1u64 << LOW_BITS) - 1;
+        let mut sorted = fingerprints.to_vec();
+        sorted.sort_by_key(|fp| fp & low_mask);
+
+        let lows: Vec<u8> = sorted.iter().map(|fp| (fp & low_mask) as u8).collect();
+        let code = *self
+            .tuple_to_code
+            .get(&lows)
+            .expect("fingerprint low bits out of codec range");
+
+        let high_bits = high_bits_of(fingerprint_bits);
+        let high_mask = (1u128 << high_bits) - 1;
+
+        let mut packed: u128 = 0;
+        for fp in &sorted {
+            let high = (*fp >> LOW_BITS) as u128 & high_mask;
+            packed = (packed << high_bits) | high;
+        }
+        (packed << self.code_bits) | code as u128
+    }
+
+    /// Inverse of `pack`: recovers the `slots` fingerprints (in sorted
+    /// order; callers only care about the resulting multiset).
+    pub fn unpack(&self, packed: u128, fingerprint_bits: u32) -> Vec<u64> {
+        let high_bits = high_bits_of(fingerprint_bits);
+        let code_mask = (1u128 << self.code_bits) - 1;
+        let code = (packed & code_mask) as usize;
+
+        let high_mask = (1u128 << high_bits) - 1;
+        let mut rest = packed >> self.code_bits;
+
+        let lows = &self.code_to_tuple[code];
+        let mut fingerprints = vec![0u64; self.slots];
+        for i in (0..self.slots).rev() {
+            let high = (rest & high_mask) as u64;
+            fingerprints[i] = (high << LOW_BITS) | lows[i] as u64;
+            rest >>= high_bits;
+        }
+        fingerprints
+    }
+
+    /// Reports whether `fingerprint` is one of the values packed into
+    /// `packed`.
+    pub fn contains(&self, packed: u128, fingerprint_bits: u32, fingerprint: u64) -> bool {
+        self.unpack(packed, fingerprint_bits).contains(&fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_unpack_round_trip_regardless_of_full_value_order() {
+        // 0x01 and 0x10 sort the other way around by low nibble than by
+        // full value, so this reproduces the case where a full-value sort
+        // and a low-nibble sort disagree.
+        let codec = SemiSortedBucket::new(4);
+        let fingerprints: [u64; 4] = [0x01, 0x10, 0x23, 0x05];
+
+        let packed = codec.pack(&fingerprints, 8);
+        let mut unpacked = codec.unpack(packed, 8);
+        unpacked.sort_unstable();
+
+        let mut expected = fingerprints.to_vec();
+        expected.sort_unstable();
+
+        assert_eq!(unpacked, expected);
+        for fp in &fingerprints {
+            assert!(codec.contains(packed, 8, *fp));
+        }
+    }
+
+    #[test]
+    fn pack_unpack_with_fingerprint_narrower_than_low_bits() {
+        // fingerprint_bits < LOW_BITS is reachable via
+        // cuckoo::Parameters::infer(); this must not underflow.
+        let codec = SemiSortedBucket::new(4);
+        let fingerprints: [u64; 4] = [1, 0, 1, 0];
+
+        let packed = codec.pack(&fingerprints, 2);
+        let mut unpacked = codec.unpack(packed, 2);
+        unpacked.sort_unstable();
+
+        let mut expected = fingerprints.to_vec();
+        expected.sort_unstable();
+
+        assert_eq!(unpacked, expected);
+    }
+}
@@ -1,3 +1,4 @@
+use super::mathutil;
 use super::FilterParameters;
 
 #[derive(Clone, Copy, Debug)]
@@ -52,7 +53,7 @@ impl Parameters {
         for _ in 0..2 {
             self.bits = self
                 .bits
-                .or_else(|| self.error.and_then(|error| Some(f64::log2(1.0 / error))));
+                .or_else(|| self.error.and_then(|error| Some(mathutil::log2(1.0 / error))));
 
             self.bits = self.bits.or_else(|| {
                 self.storage
@@ -68,7 +69,7 @@ impl Parameters {
                     .elements
                     .or_else(|| self.storage.and_then(|storage| Some(storage / bits)));
 
-                self.error = self.error.or_else(|| Some(f64::powf(2.0, -bits)));
+                self.error = self.error.or_else(|| Some(mathutil::powf(2.0, -bits)));
             });
         }
 
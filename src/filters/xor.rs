@@ -0,0 +1,155 @@
+use super::mathutil;
+use super::FilterParameters;
+
+/// XOR and binary fuse filters map each key via 3 hash functions to 3
+/// slots in an array of `f`-bit fingerprints, testing membership as
+/// `fingerprint(x) == B[h0(x)] ^ B[h1(x)] ^ B[h2(x)]`. They are built
+/// once from a static set (no insert/delete), so there is no load-factor
+/// knob: storage is a fixed multiple of the element count.
+#[derive(Clone, Copy, Debug)]
+pub struct Parameters {
+    // user defined / infered
+    error: Option<f64>,    // false positive rate
+    elements: Option<f64>, // number of elements
+    storage: Option<f64>,  // storage (bits)
+
+    // infered optimal value
+    fingerprint: Option<f64>, // fingerprint size
+
+    // hyper parameter
+    size_factor: f64, // array size relative to element count (~1.23 XOR, ~1.08-1.13 binary fuse)
+}
+
+impl FilterParameters for Parameters {
+    fn error(&self) -> Option<f64> {
+        self.error
+    }
+
+    fn elements(&self) -> Option<u64> {
+        self.elements.map(|v| v as u64)
+    }
+
+    fn storage(&self) -> Option<u64> {
+        self.storage.map(|v| v as u64)
+    }
+
+    fn bits_per_element(&self) -> Option<f64> {
+        self.storage
+            .and_then(|storage| self.elements.map(|elements| storage / elements))
+    }
+}
+
+impl Parameters {
+    /// Infers new parameters from contraints on:
+    ///
+    /// - error: the false positive rate.
+    /// - elements: the number of elements to store.
+    /// - storage: the number of bits for the filter.
+    ///
+    /// At most 2 of which may be supplied,
+    /// otherwise the system is over constrained.
+    ///
+    /// Additionally the following hyper parameter must be supplied:
+    ///
+    /// - size_factor: slot array size relative to the element count
+    ///   (~1.23 for the classic XOR filter, ~1.08-1.13 for binary fuse)
+    ///
+    /// # Returns
+    ///
+    /// A full resolved set of optimal parameters.
+    pub fn new(
+        error: Option<f64>,
+        elements: Option<u64>,
+        storage: Option<u64>,
+        size_factor: f64,
+    ) -> Parameters {
+        let mut contraints = 0;
+
+        contraints += error.is_some() as u32;
+        contraints += elements.is_some() as u32;
+        contraints += storage.is_some() as u32;
+
+        let params = Parameters {
+            error,
+            elements: elements.map(|v| v as f64),
+            storage: storage.map(|v| v as f64),
+            fingerprint: None,
+            size_factor,
+        };
+
+        if contraints == 2 {
+            params.infer()
+        } else {
+            params
+        }
+    }
+
+    pub fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint.map(|v| v as u64)
+    }
+
+    pub fn size_factor(&self) -> f64 {
+        self.size_factor
+    }
+
+    fn incomplete(&self) -> bool {
+        self.error.is_none()
+            || self.fingerprint.is_none()
+            || self.storage.is_none()
+            || self.elements.is_none()
+    }
+
+    fn infer(mut self) -> Parameters {
+        for _ in 0..4 {
+            if !self.incomplete() {
+                break;
+            }
+
+            // Infer fingerprint size, from:
+            //  - error: err ~= 2^-f
+            self.fingerprint = self.fingerprint.or_else(|| {
+                self.error
+                    .and_then(|error| Some(mathutil::ceil(mathutil::log2(1.0 / error))))
+            });
+
+            // Infer fingerprint size, from:
+            //  - storage
+            //  - elements
+            self.fingerprint = self.fingerprint.or_else(|| {
+                self.elements.and_then(|elements| {
+                    self.storage.and_then(|storage| {
+                        Some(mathutil::floor(
+                            storage / mathutil::ceil(self.size_factor * elements),
+                        ))
+                    })
+                })
+            });
+
+            self.fingerprint.map(|fingerprint| {
+                // Infer false positive rate, from:
+                //  - fingerprint size
+                self.error = self
+                    .error
+                    .or_else(|| Some(mathutil::powf(2.0, -fingerprint)));
+
+                // Infer storage, from:
+                //  - fingerprint size
+                //  - elements: storage = ceil(size_factor * elements) * f
+                self.storage = self.storage.or_else(|| {
+                    self.elements
+                        .map(|elements| mathutil::ceil(self.size_factor * elements) * fingerprint)
+                });
+
+                // Infer elements, from:
+                //  - fingerprint size
+                //  - storage
+                self.elements = self.elements.or_else(|| {
+                    self.storage
+                        .map(|storage| mathutil::floor(storage / fingerprint / self.size_factor))
+                });
+            });
+        }
+
+        self
+    }
+}
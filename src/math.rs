@@ -1,6 +1,7 @@
 use num::bigint::Sign;
 use num::rational::Ratio;
 use num::traits::cast::FromPrimitive;
+use num::traits::cast::ToPrimitive;
 use num::BigInt;
 use num::BigUint;
 use num::Integer;
@@ -30,14 +31,15 @@ fn exp2u(e: u64) -> BigUint {
 ///
 /// Integer representing 2^e
 ///
-fn exp2(e: u64) -> Int {
+pub(crate) fn exp2(e: u64) -> Int {
     let n = 1 + (e / 32) as usize;
     let mut v: Vec<u32> = vec![0; n];
     v[n - 1] = 1 << (e % 32);
     Int::from_slice(Sign::Plus, &v[..])
 }
 
-fn trucr(a: Rat, r: u64) -> Rat {
+/// Truncates `a` to `r` bits beyond the "decimal" point.
+pub(crate) fn trucr(a: Rat, r: u64) -> Rat {
     let n = (a * exp2(r)).to_integer();
     Rat::new(n, exp2(r))
 }
@@ -46,16 +48,22 @@ fn trucr(a: Rat, r: u64) -> Rat {
 ///
 /// Arguments:
 ///
-/// - base:
-/// - exp: Exponent (non-negative rational)
+/// - base: Positive rational base (fractional exponents require `base > 0`)
+/// - exp: Exponent (signed rational)
 /// - prec: Bits of precision
 ///
 /// Returns:
 ///
-/// An approximation for rational base^exp with r bits of precision.
+/// An approximation for rational base^exp with r bits of precision,
+/// or `None` if `base` is non-positive.
 pub fn pow(base: Rat, exp: Rat, prec: u64) -> Option<Rat> {
+    // Computes base^(p / 2^prec) via repeated nth-roots, one bit of `p`
+    // at a time. Only valid for `p < 2^prec` (i.e. the *fractional* part
+    // of the scaled exponent): `d` starts at `prec` and is decremented
+    // once per set/unset bit of `p`, so it underflows if `p` has more
+    // than `prec` bits. The integer part is handled separately by `pow`
+    // via plain squaring (`powi`), which needs no root at all.
     fn rec(base: &Rat, p: BigUint, d: u64, r: u64) -> Rat {
-        println!("{}", p);
         // base case (p == 0)
         if p.is_zero() {
             return Rat::from_u64(1).unwrap();
@@ -70,9 +78,31 @@ pub fn pow(base: Rat, exp: Rat, prec: u64) -> Option<Rat> {
         }
     }
 
-    let powr = (exp * exp2(prec)).to_integer().to_biguint()?;
+    if base <= Rat::zero() {
+        return None;
+    }
+
+    let negative = exp < Rat::zero();
+    let mag = if negative { -exp } else { exp };
+
+    let powr = (mag * exp2(prec)).to_integer().to_biguint()?;
 
-    Some(rec(&base, powr, prec, prec))
+    // Split the fixed-point exponent into its integer and fractional
+    // (< 2^prec) parts so `rec`'s bit-budget (`d` starting at `prec`)
+    // only ever has to cover the fractional bits; the integer part is
+    // an exact power, handled by repeated squaring instead.
+    let scale = exp2u(prec);
+    let int_part = &powr / &scale;
+    let frac_part = powr % scale;
+
+    let frac_res = rec(&base, frac_part, prec, prec);
+    let res = if int_part.is_zero() {
+        frac_res
+    } else {
+        powi(base, &int_part) * frac_res
+    };
+
+    Some(if negative { res.recip() } else { res })
 }
 
 /// Integer power.
@@ -111,9 +141,21 @@ fn nroot(a: Rat, n: BigUint, r: u64) -> Rat {
         Rat::new(n, exp2(r))
     }
 
+    // Seed the iteration close to the true root: L estimates log2(a) as
+    // the difference in bit-lengths of the numerator and denominator, so
+    // x = 2^(L/deg) lands within a small factor of a^(1/deg) regardless of
+    // how large (or small) `a` is, keeping the iteration count bounded.
+    let l = a.numer().bits() as i64 - a.denom().bits() as i64;
+    let deg = n.to_u64().unwrap_or(u64::max_value()).max(1);
+
     let n1 = n.clone() - BigUint::new(vec![1]);
     let n = Int::from_biguint(Sign::Plus, n);
-    let mut x = Rat::from_integer(Int::from_slice(Sign::Plus, &[1]));
+
+    let mut x = if l >= 0 {
+        Rat::from_integer(exp2(l as u64 / deg))
+    } else {
+        Rat::new(Int::from_slice(Sign::Plus, &[1]), exp2((-l) as u64 / deg))
+    };
 
     loop {
         let x_n = powi(x.clone(), &n1);
@@ -126,7 +168,90 @@ fn nroot(a: Rat, n: BigUint, r: u64) -> Rat {
     }
 }
 
-pub fn decimal(mut rat: Rat, r: usize) -> String {
+/// Rounding mode used when collapsing a rational to an integer
+/// (e.g. when rendering a fixed number of decimal places).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round towards zero.
+    Truncate,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceil,
+    /// Round to nearest, ties away from zero.
+    HalfUp,
+    /// Round to nearest, ties towards the even last digit (banker's rounding).
+    HalfEven,
+}
+
+/// Rounds `rat` to an integer using the given `mode`.
+fn round_to_int(rat: &Rat, mode: RoundingMode) -> Int {
+    let one = Int::from_slice(Sign::Plus, &[1]);
+    let d = rat.denom().clone();
+    let n = rat.numer().clone();
+
+    // floor(n / d) and the non-negative remainder n - q*d
+    let q = n.div_floor(&d);
+    let rem = &n - &q * &d;
+
+    match mode {
+        RoundingMode::Floor => q,
+        RoundingMode::Ceil => {
+            if rem.is_zero() {
+                q
+            } else {
+                q + one
+            }
+        }
+        RoundingMode::Truncate => {
+            if rem.is_zero() || n.sign() != Sign::Minus {
+                q
+            } else {
+                q + one
+            }
+        }
+        RoundingMode::HalfUp => {
+            let twice = &rem * Int::from_slice(Sign::Plus, &[2]);
+            match twice.cmp(&d) {
+                cmp::Ordering::Less => q,
+                cmp::Ordering::Greater => q + one,
+                // tie: away from zero
+                cmp::Ordering::Equal => {
+                    if n.sign() == Sign::Minus {
+                        q
+                    } else {
+                        q + one
+                    }
+                }
+            }
+        }
+        RoundingMode::HalfEven => {
+            let twice = &rem * Int::from_slice(Sign::Plus, &[2]);
+            match twice.cmp(&d) {
+                cmp::Ordering::Less => q,
+                cmp::Ordering::Greater => q + one,
+                // tie: round to whichever neighbor is even
+                cmp::Ordering::Equal => {
+                    if q.is_even() {
+                        q
+                    } else {
+                        q + one
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Formats `rat` as a base-10 decimal string with `r` fractional digits,
+/// rounding with `RoundingMode::HalfUp` (the historical default).
+pub fn decimal(rat: Rat, r: usize) -> String {
+    decimal_with_mode(rat, r, RoundingMode::HalfUp)
+}
+
+/// Formats `rat` as a base-10 decimal string with `r` fractional digits,
+/// using the given rounding mode.
+pub fn decimal_with_mode(mut rat: Rat, r: usize, mode: RoundingMode) -> String {
     let mut chr: Vec<char> = Vec::with_capacity(r + 32);
     let digit: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
 
@@ -134,7 +259,7 @@ pub fn decimal(mut rat: Rat, r: usize) -> String {
     for _ in 0..r {
         rat = rat * Rat::from_integer(Int::from_slice(Sign::Plus, &[10]));
     }
-    let num = rat.round().to_integer();
+    let num = round_to_int(&rat, mode);
 
     // extract digits
     let (sign, digits) = num.to_radix_le(10);
@@ -158,6 +283,139 @@ pub fn decimal(mut rat: Rat, r: usize) -> String {
     chr.iter().rev().collect::<String>()
 }
 
+/// Computes `radix^digits` as an exact integer (`radix` small, so a
+/// plain repeated multiplication is simpler than a pow-by-squaring here).
+fn exp_radix(radix: u32, digits: usize) -> Int {
+    let base = Int::from_slice(Sign::Plus, &[radix]);
+    let mut acc = Int::from_slice(Sign::Plus, &[1]);
+    for _ in 0..digits {
+        acc *= base.clone();
+    }
+    acc
+}
+
+/// Formats `rat` in an arbitrary `radix` (2..=36) with `digits` fractional
+/// places, using `0-9a-z` as the digit alphabet.
+///
+/// Mirrors `decimal`, but scales by `radix^digits` instead of `10^r` and
+/// renders via `to_radix_le(radix)`, so e.g. `radix_string(x, 16, 8)` gives
+/// a hexadecimal expansion of `x`.
+pub fn radix_string(mut rat: Rat, radix: u32, digits: usize) -> String {
+    assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+
+    let alphabet: [char; 36] = [
+        '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h',
+        'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+    ];
+
+    let mut chr: Vec<char> = Vec::with_capacity(digits + 32);
+
+    // shift and round
+    rat = rat * Rat::from_integer(exp_radix(radix, digits));
+    let num = round_to_int(&rat, RoundingMode::HalfUp);
+
+    // extract digits
+    let (sign, raw_digits) = num.to_radix_le(radix);
+
+    for i in 0..cmp::max(raw_digits.len(), digits + 1) {
+        if i == digits {
+            chr.push('.');
+        }
+        match raw_digits.get(i) {
+            Some(d) => {
+                chr.push(alphabet[*d as usize]);
+            }
+            None => chr.push('0'),
+        }
+    }
+
+    if sign == Sign::Minus {
+        chr.push('-');
+    }
+
+    chr.iter().rev().collect::<String>()
+}
+
+fn abs_rat(a: Rat) -> Rat {
+    if a < Rat::zero() {
+        -a
+    } else {
+        a
+    }
+}
+
+/// Finds the closest rational to `x` whose denominator does not exceed
+/// `max_denom`, via the continued-fraction convergent algorithm.
+///
+/// Builds convergents `h_i / k_i` with `h_i = a_i*h_{i-1} + h_{i-2}`,
+/// `k_i = a_i*k_{i-1} + k_{i-2}` (seeded `h_{-1}=1, h_{-2}=0, k_{-1}=0,
+/// k_{-2}=1`), stops at the last convergent with `k_i <= max_denom`, and
+/// checks the semiconvergent `a' = floor((max_denom - k_{i-2}) / k_{i-1})`
+/// in case the half-step is a closer approximation.
+pub fn best_approx(x: Rat, max_denom: &Int) -> Rat {
+    let negative = x < Rat::zero();
+    let x = if negative { -x } else { x };
+
+    let zero = Int::zero();
+    let one = Int::from_slice(Sign::Plus, &[1]);
+
+    let mut h_prev2 = zero.clone();
+    let mut h_prev1 = one.clone();
+    let mut k_prev2 = one.clone();
+    let mut k_prev1 = zero.clone();
+
+    let mut h = h_prev1.clone();
+    let mut k = k_prev1.clone();
+
+    let mut rem = x.clone();
+    loop {
+        let a_i = rem.to_integer(); // rem >= 0, so truncation is the floor
+        let h_i = a_i.clone() * h_prev1.clone() + h_prev2.clone();
+        let k_i = a_i.clone() * k_prev1.clone() + k_prev2.clone();
+
+        if &k_i > max_denom {
+            // semiconvergent check: is the half-step towards this
+            // convergent a better approximation than the last full one?
+            let a_prime = (max_denom - &k_prev2).div_floor(&k_prev1);
+            let h_s = a_prime.clone() * h_prev1.clone() + h_prev2.clone();
+            let k_s = a_prime * k_prev1.clone() + k_prev2.clone();
+
+            let d_prev = abs_rat(x.clone() - Rat::new(h_prev1.clone(), k_prev1.clone()));
+            let d_semi = abs_rat(x - Rat::new(h_s.clone(), k_s.clone()));
+
+            if d_semi < d_prev {
+                h = h_s;
+                k = k_s;
+            } else {
+                h = h_prev1;
+                k = k_prev1;
+            }
+            break;
+        }
+
+        h = h_i.clone();
+        k = k_i.clone();
+
+        let frac = rem - Rat::from_integer(a_i);
+        if frac.is_zero() {
+            break;
+        }
+
+        h_prev2 = h_prev1;
+        h_prev1 = h_i;
+        k_prev2 = k_prev1;
+        k_prev1 = k_i;
+        rem = frac.recip();
+    }
+
+    let result = Rat::new(h, k);
+    if negative {
+        -result
+    } else {
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +458,73 @@ mod tests {
             assert_eq!(decimal(r.unwrap(), n), res);
         }
     }
+
+    fn pi_truncated() -> Rat {
+        // pi, truncated to 31 decimal digits: exact as a fraction, and
+        // far more precise than anything a denominator <= 1000 could
+        // resolve, so it stands in for "true" pi in these tests.
+        let numer: Int = "31415926535897932384626433832795".parse().unwrap();
+        let denom: Int = "10000000000000000000000000000000".parse().unwrap();
+        Rat::new(numer, denom)
+    }
+
+    #[test]
+    fn test_best_approx_pi() {
+        // The textbook convergent 355/113 (accurate to ~6 decimal places).
+        let max_denom = Int::from_slice(Sign::Plus, &[1000]);
+        let approx = best_approx(pi_truncated(), &max_denom);
+        assert_eq!(
+            approx,
+            Rat::new(
+                Int::from_slice(Sign::Plus, &[355]),
+                Int::from_slice(Sign::Plus, &[113]),
+            )
+        );
+    }
+
+    #[test]
+    fn test_best_approx_negative() {
+        let max_denom = Int::from_slice(Sign::Plus, &[1000]);
+        let approx = best_approx(-pi_truncated(), &max_denom);
+        assert_eq!(
+            approx,
+            -Rat::new(
+                Int::from_slice(Sign::Plus, &[355]),
+                Int::from_slice(Sign::Plus, &[113]),
+            )
+        );
+    }
+
+    #[test]
+    fn test_best_approx_exact_when_denom_fits() {
+        let half = Rat::new(Int::from_slice(Sign::Plus, &[1]), Int::from_slice(Sign::Plus, &[2]));
+        let max_denom = Int::from_slice(Sign::Plus, &[1000]);
+        assert_eq!(best_approx(half.clone(), &max_denom), half);
+    }
+
+    #[test]
+    fn test_pow_integer_exponents() {
+        // `pow` with an integer exponent should be exact, regardless of
+        // sign or magnitude: mag's fractional part is 0, so the integer
+        // part goes through `powi` untouched and `rec` only contributes
+        // its base case (1).
+        let two = Rat::from_integer(Int::from_slice(Sign::Plus, &[2]));
+        let tests: Vec<(i64, Rat)> = vec![
+            (-2, Rat::new(Int::from_slice(Sign::Plus, &[1]), Int::from_slice(Sign::Plus, &[4]))),
+            (-1, Rat::new(Int::from_slice(Sign::Plus, &[1]), Int::from_slice(Sign::Plus, &[2]))),
+            (0, Rat::from_integer(Int::from_slice(Sign::Plus, &[1]))),
+            (1, Rat::from_integer(Int::from_slice(Sign::Plus, &[2]))),
+            (2, Rat::from_integer(Int::from_slice(Sign::Plus, &[4]))),
+            (3, Rat::from_integer(Int::from_slice(Sign::Plus, &[8]))),
+        ];
+        for (e, expect) in tests {
+            let exp = if e < 0 {
+                -Rat::from_integer(Int::from_slice(Sign::Plus, &[(-e) as u32]))
+            } else {
+                Rat::from_integer(Int::from_slice(Sign::Plus, &[e as u32]))
+            };
+            let got = pow(two.clone(), exp, 64).unwrap();
+            assert_eq!(got, expect, "2^{} via pow()", e);
+        }
+    }
 }
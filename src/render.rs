@@ -1,12 +1,17 @@
+//! The yew frontend.
+
 use log::info;
 
 use regex::Regex;
 
+use stdweb::js;
+use stdweb::web::window;
+
 use yew::{html, Component, ComponentLink, Html, ShouldRender};
 
 use std::option::NoneError;
 
-use super::filters::{bloom, cuckoo, theory, FilterParameters};
+use super::filters::{bloom, counting_bloom, cuckoo, morton, theory, xor, FilterParameters};
 
 /// Specified parameters for all filters
 #[derive(Debug)]
@@ -16,6 +21,10 @@ pub struct Params {
     storage: String,
     cuckoo_hashes: u64,
     cuckoo_slots: u64,
+    morton_logical_slots: u64,
+    morton_physical_slots: u64,
+    morton_buckets_per_block: u64,
+    counter_bits: u64,
 }
 
 pub struct Model {
@@ -33,6 +42,10 @@ pub enum Msg {
     UpdateStorage(String),
     UpdateCuckooHashes(u64),
     UpdateCuckooSlots(u64),
+    UpdateMortonLogicalSlots(u64),
+    UpdateMortonPhysicalSlots(u64),
+    UpdateMortonBucketsPerBlock(u64),
+    UpdateCounterBits(u64),
 }
 
 fn is_space(s: &str) -> bool {
@@ -133,6 +146,107 @@ fn sep_1000(v: u64) -> String {
     }
 }
 
+/// Hardcoded defaults used both when no permalink is present at all, and
+/// field-by-field inside `decode_permalink` when a field fails to parse.
+const DEFAULT_ERROR: &str = "0.0000001";
+const DEFAULT_ELEMENTS: &str = "4K";
+const DEFAULT_STORAGE: &str = "";
+const DEFAULT_CUCKOO_HASHES: u64 = 2;
+const DEFAULT_CUCKOO_SLOTS: u64 = 4;
+
+/// Percent-encodes everything but the URL-safe "unreserved" characters,
+/// so a value containing `&`, `=`, or `#` round-trips through a fragment.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Inverse of `percent_encode`. Returns `None` on a malformed `%XX`
+/// escape or invalid UTF-8.
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = u8::from_str_radix(s.get(i + 1..i + 3)?, 16).ok()?;
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Serializes the shareable subset of `Params` into a URL fragment, so a
+/// configuration can be bookmarked or linked to directly.
+fn encode_permalink(params: &Params) -> String {
+    format!(
+        "error={}&elements={}&storage={}&cuckoo_hashes={}&cuckoo_slots={}",
+        percent_encode(&params.error),
+        percent_encode(&params.elements),
+        percent_encode(&params.storage),
+        params.cuckoo_hashes,
+        params.cuckoo_slots,
+    )
+}
+
+/// Parses a URL fragment produced by `encode_permalink`. Returns `None`
+/// when the fragment is absent, when any `&`-separated piece of it lacks
+/// an `=`, or when it names none of `error`/`elements`/`storage`; the
+/// caller then falls back to the hardcoded defaults wholesale. Otherwise
+/// an individually malformed field falls back to that field's hardcoded
+/// default, while a blank one stays blank, reusing `parse_error`/
+/// `parse_elements`/`parse_storage` so "malformed" means the same thing
+/// here as it does when validating direct user input.
+fn decode_permalink(fragment: &str) -> Option<(String, String, String, u64, u64)> {
+    let mut error = None;
+    let mut elements = None;
+    let mut storage = None;
+    let mut cuckoo_hashes = None;
+    let mut cuckoo_slots = None;
+
+    for pair in fragment.trim_start_matches('#').split('&') {
+        let mut it = pair.splitn(2, '=');
+        let key = it.next()?;
+        let value = it.next()?;
+        match key {
+            "error" => error = percent_decode(value),
+            "elements" => elements = percent_decode(value),
+            "storage" => storage = percent_decode(value),
+            "cuckoo_hashes" => cuckoo_hashes = value.parse().ok(),
+            "cuckoo_slots" => cuckoo_slots = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    if error.is_none() && elements.is_none() && storage.is_none() {
+        return None;
+    }
+
+    let error = error
+        .filter(|s| is_space(s) || parse_error(s).is_ok())
+        .unwrap_or_else(|| DEFAULT_ERROR.to_string());
+    let elements = elements
+        .filter(|s| is_space(s) || parse_elements(s).is_ok())
+        .unwrap_or_else(|| DEFAULT_ELEMENTS.to_string());
+    let storage = storage
+        .filter(|s| s.is_empty() || parse_storage(s).is_ok())
+        .unwrap_or_else(|| DEFAULT_STORAGE.to_string());
+    let cuckoo_hashes = cuckoo_hashes.unwrap_or(DEFAULT_CUCKOO_HASHES);
+    let cuckoo_slots = cuckoo_slots.unwrap_or(DEFAULT_CUCKOO_SLOTS);
+
+    Some((error, elements, storage, cuckoo_hashes, cuckoo_slots))
+}
+
 fn count_some<T, E>(v: Result<Option<T>, E>) -> u32 {
     if let Ok(v) = v {
         v.is_some() as u32
@@ -259,7 +373,7 @@ impl Model {
         let storage = if err { None } else { storage.unwrap() };
         let elements = if err { None } else { elements.unwrap() };
 
-        let params = bloom::Parameters::new(error, elements, storage, None);
+        let params = bloom::Parameters::new(error, elements, storage, None, None);
 
         html! {
             <table class="mono">
@@ -281,6 +395,66 @@ impl Model {
         }
     }
 
+    fn render_counting_bloom(
+        &self,
+        storage: Result<Option<u64>, NoneError>,
+        elements: Result<Option<u64>, NoneError>,
+        error: Result<Option<f64>, NoneError>,
+        counter_bits: u64,
+    ) -> Html {
+        let err = storage.is_err() | elements.is_err() | error.is_err();
+
+        let error = if err { None } else { error.unwrap() };
+        let storage = if err { None } else { storage.unwrap() };
+        let elements = if err { None } else { elements.unwrap() };
+
+        let params =
+            counting_bloom::Parameters::new(error, elements, storage, None, Some(counter_bits));
+
+        html! {
+            <table class="mono">
+                { render_param_storage(&params) }
+                { render_param_elements(&params) }
+                { render_param_error(&params) }
+                { render_param_bits(&params) }
+                <tr></tr>
+                <tr class="specific">
+                    <td>{"Hashes"}</td>
+                    <td>{":"}</td>
+                    <td>{ if let Some(hashes) = params.hashes() {
+                        format!("{}", hashes)
+                    } else {
+                        "".to_string()
+                    } }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Counter width"}</td>
+                    <td>{":"}</td>
+                    <td>{ format!("{} bits", params.counter_bits()) }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Overflow probability"}</td>
+                    <td>{":"}</td>
+                    <td>{ if let Some(overflow) = params.overflow_probability() {
+                        let flagged = error.map_or(false, |error| overflow > error);
+                        if flagged {
+                            format!("{} (exceeds target FPR)", format_error(overflow))
+                        } else {
+                            format_error(overflow)
+                        }
+                    } else {
+                        "".to_string()
+                    } }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Penalty vs. plain Bloom"}</td>
+                    <td>{":"}</td>
+                    <td>{ format!("{:.2}x bits/item", params.bits_per_element_penalty()) }</td>
+                </tr>
+            </table>
+        }
+    }
+
     fn render_cuckoo(
         &self,
         storage: Result<Option<u64>, NoneError>,
@@ -335,6 +509,249 @@ impl Model {
         }
     }
 
+    fn render_morton(
+        &self,
+        storage: Result<Option<u64>, NoneError>,
+        elements: Result<Option<u64>, NoneError>,
+        error: Result<Option<f64>, NoneError>,
+        logical_slots: u64,
+        physical_slots: u64,
+        buckets_per_block: u64,
+    ) -> Html {
+        let err = storage.is_err() | elements.is_err() | error.is_err();
+
+        let error = if err { None } else { error.unwrap() };
+        let storage = if err { None } else { storage.unwrap() };
+        let elements = if err { None } else { elements.unwrap() };
+
+        let params = morton::Parameters::new(
+            error,
+            elements,
+            storage,
+            logical_slots,
+            physical_slots,
+            buckets_per_block,
+            2,
+            1,
+            0.95,
+        );
+
+        html! {
+            <table class="mono">
+                { render_param_storage(&params) }
+                { render_param_elements(&params) }
+                { render_param_error(&params) }
+                { render_param_bits(&params) }
+                <tr class="specific">
+                    <td>{"Fingerprint size"}</td>
+                    <td>{":"}</td>
+                    <td>{ if let Some(fingerprint) = params.fingerprint() {
+                        format!("{} bits", fingerprint)
+                    } else {
+                        "".to_string()
+                    } }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Blocks"}</td>
+                    <td>{":"}</td>
+                    <td>{ if let Some(blocks) = params.blocks() {
+                        blocks.to_string()
+                    } else {
+                        "".to_string()
+                    } }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Logical slots per bucket"}</td>
+                    <td>{":"}</td>
+                    <td>{ params.logical_slots() }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Physical slots per block"}</td>
+                    <td>{":"}</td>
+                    <td>{ params.physical_slots() }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Logical buckets per block"}</td>
+                    <td>{":"}</td>
+                    <td>{ params.buckets_per_block() }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"FCA / OTA overhead"}</td>
+                    <td>{":"}</td>
+                    <td>{ format!("{} + {} bits/bucket", params.fca_bits(), params.ota_bits()) }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Load factor"}</td>
+                    <td>{":"}</td>
+                    <td>{ format!("{:.2}", params.load_factor()) }</td>
+                </tr>
+            </table>
+        }
+    }
+
+    fn render_xor(
+        &self,
+        storage: Result<Option<u64>, NoneError>,
+        elements: Result<Option<u64>, NoneError>,
+        error: Result<Option<f64>, NoneError>,
+    ) -> Html {
+        let err = storage.is_err() | elements.is_err() | error.is_err();
+
+        let error = if err { None } else { error.unwrap() };
+        let storage = if err { None } else { storage.unwrap() };
+        let elements = if err { None } else { elements.unwrap() };
+
+        // classic XOR filter sizing; binary fuse filters push this down to ~1.08-1.13
+        let params = xor::Parameters::new(error, elements, storage, 1.23);
+
+        html! {
+            <table class="mono">
+                { render_param_storage(&params) }
+                { render_param_elements(&params) }
+                { render_param_error(&params) }
+                { render_param_bits(&params) }
+                <tr class="specific">
+                    <td>{"Fingerprint size"}</td>
+                    <td>{":"}</td>
+                    <td>{ if let Some(fingerprint) = params.fingerprint() {
+                        format!("{} bits", fingerprint)
+                    } else {
+                        "".to_string()
+                    } }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Size factor"}</td>
+                    <td>{":"}</td>
+                    <td>{ format!("{:.2}", params.size_factor()) }</td>
+                </tr>
+                <tr class="specific">
+                    <td>{"Construction"}</td>
+                    <td>{":"}</td>
+                    <td>{"one-shot, static set (no insert/delete)"}</td>
+                </tr>
+            </table>
+        }
+    }
+
+    fn render_comparison(
+        &self,
+        storage: Result<Option<u64>, NoneError>,
+        elements: Result<Option<u64>, NoneError>,
+        error: Result<Option<f64>, NoneError>,
+    ) -> Html {
+        let err = storage.is_err() | elements.is_err() | error.is_err();
+
+        let error = if err { None } else { error.unwrap() };
+        let storage = if err { None } else { storage.unwrap() };
+        let elements = if err { None } else { elements.unwrap() };
+
+        let theory = theory::Parameters::new(error, elements, storage);
+        let bloom = bloom::Parameters::new(error, elements, storage, None, None);
+        let counting = counting_bloom::Parameters::new(
+            error,
+            elements,
+            storage,
+            None,
+            Some(self.params.counter_bits),
+        );
+        let cuckoo = cuckoo::Parameters::new(
+            error,
+            elements,
+            storage,
+            self.params.cuckoo_hashes,
+            self.params.cuckoo_slots,
+            0.95,
+            true,
+        );
+        let morton = morton::Parameters::new(
+            error,
+            elements,
+            storage,
+            self.params.morton_logical_slots,
+            self.params.morton_physical_slots,
+            self.params.morton_buckets_per_block,
+            2,
+            1,
+            0.95,
+        );
+        let xor = xor::Parameters::new(error, elements, storage, 1.23);
+
+        let theory_bits = theory.bits_per_element();
+
+        let rows: Vec<(&str, Option<f64>, Option<u64>, Option<f64>, &str)> = vec![
+            (
+                "Theoretic limit",
+                theory.bits_per_element(),
+                theory.storage(),
+                theory.error(),
+                "information-theoretic bound",
+            ),
+            (
+                "Bloom",
+                bloom.bits_per_element(),
+                bloom.storage(),
+                bloom.error(),
+                "O(k) insert/lookup, no delete",
+            ),
+            (
+                "Counting Bloom",
+                counting.bits_per_element(),
+                counting.storage(),
+                counting.error(),
+                "O(k) insert/lookup/delete",
+            ),
+            (
+                "Cuckoo",
+                cuckoo.bits_per_element(),
+                cuckoo.storage(),
+                cuckoo.error(),
+                "O(1) amortized insert, O(1) lookup/delete",
+            ),
+            (
+                "Morton",
+                morton.bits_per_element(),
+                morton.storage(),
+                morton.error(),
+                "O(1) amortized insert, O(1) lookup/delete, higher load factor",
+            ),
+            (
+                "XOR / Binary Fuse",
+                xor.bits_per_element(),
+                xor.storage(),
+                xor.error(),
+                "static build, O(1) lookup, no insert/delete",
+            ),
+        ];
+
+        html! {
+            <table class="mono">
+                <tr>
+                    <th>{"Filter"}</th>
+                    <th>{"Bits/item"}</th>
+                    <th>{"Storage"}</th>
+                    <th>{"FPR"}</th>
+                    <th>{"Overhead vs. theory"}</th>
+                    <th>{"Characteristics"}</th>
+                </tr>
+                { for rows.iter().map(|(name, bits, storage, fpr, characteristics)| html! {
+                    <tr class="specific">
+                        <td>{ name }</td>
+                        <td>{ bits.map(|b| format!("{:.2}", b)).unwrap_or_default() }</td>
+                        <td>{ storage.map(sep_1000).unwrap_or_default() }</td>
+                        <td>{ fpr.map(format_error).unwrap_or_default() }</td>
+                        <td>{
+                            match (bits, theory_bits) {
+                                (Some(b), Some(t)) if t > 0.0 => format!("{:.2}x", b / t),
+                                _ => "".to_string(),
+                            }
+                        }</td>
+                        <td>{ characteristics }</td>
+                    </tr>
+                }) }
+            </table>
+        }
+    }
+
     fn render_input(
         &self,
         storage: Result<Option<u64>, NoneError>,
@@ -434,16 +851,37 @@ impl Component for Model {
     type Properties = ();
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Model {
-            link,
-            params: Params {
-                error: "0.0000001".to_string(),
-                elements: "4K".to_string(),
-                storage: "".to_string(),
-                cuckoo_hashes: 2,
-                cuckoo_slots: 4,
+        let fragment = window()
+            .location()
+            .and_then(|location| location.hash().ok())
+            .unwrap_or_default();
+
+        let params = match decode_permalink(&fragment) {
+            Some((error, elements, storage, cuckoo_hashes, cuckoo_slots)) => Params {
+                error,
+                elements,
+                storage,
+                cuckoo_hashes,
+                cuckoo_slots,
+                morton_logical_slots: 4,
+                morton_physical_slots: 7,
+                morton_buckets_per_block: 4,
+                counter_bits: 4,
             },
-        }
+            None => Params {
+                error: DEFAULT_ERROR.to_string(),
+                elements: DEFAULT_ELEMENTS.to_string(),
+                storage: DEFAULT_STORAGE.to_string(),
+                cuckoo_hashes: DEFAULT_CUCKOO_HASHES,
+                cuckoo_slots: DEFAULT_CUCKOO_SLOTS,
+                morton_logical_slots: 4,
+                morton_physical_slots: 7,
+                morton_buckets_per_block: 4,
+                counter_bits: 4,
+            },
+        };
+
+        Model { link, params }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
@@ -463,7 +901,30 @@ impl Component for Model {
             Msg::UpdateCuckooSlots(n) => {
                 self.params.cuckoo_slots = n;
             }
+            Msg::UpdateMortonLogicalSlots(n) => {
+                self.params.morton_logical_slots = n;
+            }
+            Msg::UpdateMortonPhysicalSlots(n) => {
+                self.params.morton_physical_slots = n;
+            }
+            Msg::UpdateMortonBucketsPerBlock(n) => {
+                self.params.morton_buckets_per_block = n;
+            }
+            Msg::UpdateCounterBits(n) => {
+                self.params.counter_bits = n;
+            }
+        }
+
+        // keep the permalink in sync so the current configuration can be
+        // bookmarked or shared from the address bar
+        //
+        // stdweb's `Location` is read-only (no `set_hash`), so the hash is
+        // updated through the DOM directly.
+        let hash = encode_permalink(&self.params);
+        js! { @(no_return)
+            window.location.hash = @{hash};
         }
+
         true
     }
 
@@ -531,16 +992,96 @@ impl Component for Model {
                     </fieldset>
                     */
                 </div>
+                <div>
+                    <h4>{"Morton Filter"}</h4>
+                    {self.render_morton(
+                        storage,
+                        elements,
+                        error,
+                        self.params.morton_logical_slots,
+                        self.params.morton_physical_slots,
+                        self.params.morton_buckets_per_block,
+                    )}
+                    /*
+                    <br></br>
+                    <fieldset>
+                        <legend>{"Morton Filter Hyperparameters:"}</legend>
+                        <table>
+                            <tr>
+                                <td>{"Logical slots per bucket"}</td>
+                                <td>{ ":" }</td>
+                                <td style="width: 2em">{ self.params.morton_logical_slots }</td>
+                                <td>
+                                    <input type="range" min="2" max="8" value="4" class="slider" oninput=self.link.callback(move |e: html::InputData| {
+                                        Msg::UpdateMortonLogicalSlots(e.value.parse().unwrap())
+                                    })></input>
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>{"Physical slots per block"}</td>
+                                <td>{ ":" }</td>
+                                <td style="width: 2em">{ self.params.morton_physical_slots }</td>
+                                <td>
+                                    <input type="range" min="4" max="32" value="7" class="slider" oninput=self.link.callback(move |e: html::InputData| {
+                                        Msg::UpdateMortonPhysicalSlots(e.value.parse().unwrap())
+                                    })></input>
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>{"Logical buckets per block"}</td>
+                                <td>{ ":" }</td>
+                                <td style="width: 2em">{ self.params.morton_buckets_per_block }</td>
+                                <td>
+                                    <input type="range" min="1" max="8" value="4" class="slider" oninput=self.link.callback(move |e: html::InputData| {
+                                        Msg::UpdateMortonBucketsPerBlock(e.value.parse().unwrap())
+                                    })></input>
+                                </td>
+                            </tr>
+                        </table>
+                    </fieldset>
+                    */
+                </div>
+                <div>
+                    <h4>{"XOR / Binary Fuse Filter"}</h4>
+                    {self.render_xor(storage, elements, error)}
+                </div>
                 <div>
                     <h4>{"Bloom Filter"}</h4>
                     {self.render_bloom(storage, elements, error)}
                 </div>
+                <div>
+                    <h4>{"Counting Bloom Filter"}</h4>
+                    {self.render_counting_bloom(storage, elements, error, self.params.counter_bits)}
+                    /*
+                    <br></br>
+                    <fieldset>
+                        <legend>{"Counting Bloom Filter Hyperparameters:"}</legend>
+                        <table>
+                            <tr>
+                                <td>{"Counter width"}</td>
+                                <td>{ ":" }</td>
+                                <td style="width: 2em">{ self.params.counter_bits }</td>
+                                <td>
+                                    <input type="range" min="1" max="8" value="4" class="slider" oninput=self.link.callback(move |e: html::InputData| {
+                                        Msg::UpdateCounterBits(e.value.parse().unwrap())
+                                    })></input>
+                                </td>
+                            </tr>
+                        </table>
+                    </fieldset>
+                    */
+                </div>
+                <div>
+                    <h4>{"Comparison"}</h4>
+                    {self.render_comparison(storage, elements, error)}
+                </div>
                 <h4>{"Resources"}</h4>
                 <ul>
                     <li><a href="https://en.wikipedia.org/wiki/Bloom_filter">{"Bloom filter (wikipedia)"}</a></li>
                     <li><a href="https://en.wikipedia.org/wiki/Cuckoo_filter">{"Cuckoo filter (wikipedia)"}</a></li>
                     <li><a href="https://www.cs.cmu.edu/~dga/papers/cuckoo-conext2014.pdf">{"Cuckoo Filter: Practically Better Than Bloom"}</a></li>
                     <li><a href="https://www.vldb.org/pvldb/vol11/p1041-breslow.pdf">{"Morton Filters: Faster, Space-Efficient Cuckoo Filters via Biasing, Compression, and Decoupled Logical Sparsity"}</a></li>
+                    <li><a href="https://arxiv.org/abs/1912.08258">{"Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters"}</a></li>
                 </ul>
                 <footer>
                     <hr></hr>
@@ -0,0 +1,203 @@
+//! Arbitrary-precision transcendental functions (`exp`, `ln`, and
+//! real-exponent `pow`), built on the same truncate-to-r-bits fixed-point
+//! style as `math::nroot`.
+
+use num::bigint::Sign;
+use num::traits::cast::FromPrimitive;
+use num::Zero;
+
+use crate::math::{exp2, trucr, Int, Rat};
+
+/// Extra bits of working precision carried through the Taylor sums to
+/// absorb truncation error before the final result is rounded to `prec`.
+const GUARD: u64 = 32;
+
+fn abs(a: Rat) -> Rat {
+    if a < Rat::zero() {
+        -a
+    } else {
+        a
+    }
+}
+
+fn two() -> Rat {
+    Rat::from_integer(Int::from_slice(Sign::Plus, &[2]))
+}
+
+/// `exp(x)` to `prec` bits of precision.
+///
+/// Range-reduces `x` by halving until `|x|/2^k < 1/2`, sums the Taylor
+/// series for `e^(x/2^k)`, then squares the result `k` times to undo the
+/// reduction.
+pub fn exp(x: Rat, prec: u64) -> Rat {
+    if x.is_zero() {
+        return Rat::from_integer(Int::from_slice(Sign::Plus, &[1]));
+    }
+
+    let r = prec + GUARD;
+    let half = Rat::new(Int::from_slice(Sign::Plus, &[1]), Int::from_slice(Sign::Plus, &[2]));
+
+    // find the smallest k with |x|/2^k < 1/2
+    let mut k: u64 = 0;
+    let mut mag = abs(x.clone());
+    while mag >= half {
+        mag = mag / two();
+        k += 1;
+    }
+    let y = x / Rat::from_integer(exp2(k));
+
+    // S = sum_{i>=0} y^i / i!, truncated to r bits, until a term is small
+    let threshold = Rat::new(Int::from_slice(Sign::Plus, &[1]), exp2(r));
+    let mut sum = Rat::zero();
+    let mut term = Rat::from_integer(Int::from_slice(Sign::Plus, &[1]));
+    let mut i: u64 = 0;
+    loop {
+        term = trucr(term, r);
+        sum = trucr(sum + term.clone(), r);
+        if abs(term.clone()) < threshold {
+            break;
+        }
+        i += 1;
+        term = term * y.clone() / Rat::from_integer(Int::from_u64(i).unwrap());
+    }
+
+    // undo the range reduction: e^x = S^(2^k)
+    for _ in 0..k {
+        sum = trucr(sum.clone() * sum, r);
+    }
+
+    trucr(sum, prec)
+}
+
+/// Sums `2*(t + t^3/3 + t^5/5 + ...)` to `r` bits, stopping once a term
+/// drops below `2^-r`. This is `ln((1+t)/(1-t))`, the atanh series used
+/// both to compute `ln` of a normalized mantissa and `LN2` itself.
+fn atanh_series(t: Rat, r: u64) -> Rat {
+    let threshold = Rat::new(Int::from_slice(Sign::Plus, &[1]), exp2(r));
+    let t2 = trucr(t.clone() * t.clone(), r);
+
+    let mut sum = Rat::zero();
+    let mut term = t;
+    let mut i: u64 = 1;
+    loop {
+        term = trucr(term, r);
+        let addend = term.clone() / Rat::from_integer(Int::from_u64(i).unwrap());
+        sum = trucr(sum + addend.clone(), r);
+        if abs(addend) < threshold {
+            break;
+        }
+        term = term * t2.clone();
+        i += 2;
+    }
+
+    trucr(sum * two(), r)
+}
+
+/// `ln(x)` to `prec` bits of precision. Requires `x > 0`.
+pub fn ln(x: Rat, prec: u64) -> Option<Rat> {
+    if x <= Rat::zero() {
+        return None;
+    }
+    let one = Rat::from_integer(Int::from_slice(Sign::Plus, &[1]));
+    if x == one {
+        return Some(Rat::zero());
+    }
+
+    let r = prec + GUARD;
+
+    // normalize x = m * 2^e with m in [1, 2), seeded from the
+    // bit-length difference of numerator and denominator.
+    let l = x.numer().bits() as i64 - x.denom().bits() as i64 - 1;
+    let mut e = l;
+    let mut m = if e >= 0 {
+        x.clone() / Rat::from_integer(exp2(e as u64))
+    } else {
+        x.clone() * Rat::from_integer(exp2((-e) as u64))
+    };
+
+    let two_r = two();
+    while m >= two_r {
+        m = m / two_r.clone();
+        e += 1;
+    }
+    while m < one {
+        m = m * two_r.clone();
+        e -= 1;
+    }
+
+    let t = trucr((m.clone() - one.clone()) / (m + one), r);
+    let ln_m = atanh_series(t, r);
+
+    let ln2 = atanh_series(
+        trucr(Rat::new(Int::from_slice(Sign::Plus, &[1]), Int::from_slice(Sign::Plus, &[3])), r),
+        r,
+    );
+
+    let result = ln_m + Rat::from_integer(Int::from_i64(e).unwrap()) * ln2;
+    Some(trucr(result, prec))
+}
+
+/// `base^exp` for any real `exp`, computed as `exp(exp * ln(base))`.
+/// Requires `base > 0`.
+pub fn powf(base: Rat, exp_: Rat, prec: u64) -> Option<Rat> {
+    let r = prec + GUARD;
+    let l = ln(base, r)?;
+    Some(exp(exp_ * l, prec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::decimal;
+
+    fn rat_i64(v: i64) -> Rat {
+        Rat::from_integer(Int::from_i64(v).unwrap())
+    }
+
+    #[test]
+    fn exp_of_one_is_e() {
+        // e = 2.718281828459045235360287471352662497757247...
+        let r = exp(rat_i64(1), 64);
+        assert_eq!(decimal(r, 15), "2.718281828459045");
+    }
+
+    #[test]
+    fn exp_of_zero_is_exact_one() {
+        let r = exp(Rat::zero(), 64);
+        assert_eq!(r, rat_i64(1));
+    }
+
+    #[test]
+    fn exp_of_negative_is_reciprocal_ish() {
+        // e^-1 = 0.367879441171442321595523770161460867...
+        let r = exp(rat_i64(-1), 64);
+        assert_eq!(decimal(r, 15), "0.367879441171442");
+    }
+
+    #[test]
+    fn ln_of_two() {
+        // ln(2) = 0.69314718055994530941723212145818...
+        let r = ln(rat_i64(2), 64).unwrap();
+        assert_eq!(decimal(r, 15), "0.693147180559945");
+    }
+
+    #[test]
+    fn ln_of_one_is_exact_zero() {
+        assert_eq!(ln(rat_i64(1), 64), Some(Rat::zero()));
+    }
+
+    #[test]
+    fn ln_of_non_positive_is_none() {
+        assert_eq!(ln(Rat::zero(), 64), None);
+        assert_eq!(ln(rat_i64(-1), 64), None);
+    }
+
+    #[test]
+    fn exp_ln_round_trip() {
+        // exp(ln(x)) should recover x to within the requested precision.
+        let x = rat_i64(5);
+        let l = ln(x.clone(), 64).unwrap();
+        let r = exp(l, 64);
+        assert_eq!(decimal(r, 10), decimal(x, 10));
+    }
+}